@@ -0,0 +1,64 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::{
+    program::{invoke, invoke_signed},
+    system_instruction,
+};
+
+/// Loads the `T` account at a PDA passed as a remaining account, creating it
+/// with default data first if it does not exist yet. Used by batch
+/// instructions, where `init` is not available.
+///
+/// Like `init`, a PDA that already holds lamports is topped up to rent
+/// exemption, allocated and assigned instead, as `create_account` would fail.
+pub fn init_or_load<'info, T>(
+    account: &AccountInfo<'info>,
+    seeds: &[&[u8]],
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<Account<'info, T>>
+where
+    T: AccountSerialize + AccountDeserialize + AnchorSerialize + Owner + Clone + Default,
+{
+    if account.data_is_empty() {
+        let space = 8 + T::default().try_to_vec().unwrap().len();
+        let lamports = Rent::get()?.minimum_balance(space);
+        let current_lamports = account.lamports();
+        if current_lamports == 0 {
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer.key,
+                    account.key,
+                    lamports,
+                    space as u64,
+                    &T::owner(),
+                ),
+                &[payer.clone(), account.clone(), system_program.clone()],
+                &[seeds],
+            )?;
+        } else {
+            let required_lamports = lamports.max(1).saturating_sub(current_lamports);
+            if required_lamports > 0 {
+                invoke(
+                    &system_instruction::transfer(payer.key, account.key, required_lamports),
+                    &[payer.clone(), account.clone(), system_program.clone()],
+                )?;
+            }
+            invoke_signed(
+                &system_instruction::allocate(account.key, space as u64),
+                &[account.clone(), system_program.clone()],
+                &[seeds],
+            )?;
+            invoke_signed(
+                &system_instruction::assign(account.key, &T::owner()),
+                &[account.clone(), system_program.clone()],
+                &[seeds],
+            )?;
+        }
+
+        let mut data = account.try_borrow_mut_data()?;
+        let mut writer: &mut [u8] = &mut data;
+        T::default().try_serialize(&mut writer)?;
+    }
+
+    Account::try_from(account)
+}
@@ -1,62 +1,93 @@
-use anchor_lang::prelude::*;
-
-#[account]
-pub struct Pool {
-    /// Priviledged account.
-    pub authority: Pubkey,
-    /// Nonce to derive the program-derived address owning the vaults.
-    pub nonce: u8,
-    /// Paused state of the program
-    pub paused: bool,
-    /// Mint of the token that can be staked.
-    pub staking_mint: Pubkey,
-    /// Vault to store staked tokens.
-    pub staking_vault: Pubkey,
-    /// Mint of the reward A token.
-    pub reward_mint: Pubkey,
-    /// Vault to store reward A tokens.
-    pub reward_vault: Pubkey,
-    /// The period which rewards are linearly distributed.
-    pub reward_duration: u64,
-    /// The timestamp at which the current reward period ends.
-    pub reward_duration_end: u64,
-    /// Period to lock staked token and rewards
-    pub lock_period: u64,
-    /// The last time reward states were updated.
-    pub last_update_time: u64,
-    /// Rate of reward A distribution.
-    pub reward_rate: u64,
-    /// Last calculated reward A per pool token.
-    pub reward_per_token_stored: u128,
-    /// Users staked
-    pub user_stake_count: u32,
-    /// Total staked amount
-    pub total_staked: u64,
-    /// Calculate tier or not
-    pub no_tier: bool,
-    /// authorized funders
-    /// [] because short size, fixed account size, and ease of use on
-    /// client due to auto generated account size property
-    pub funders: [Pubkey; 5],
-}
-
-#[account]
-#[derive(Default)]
-pub struct User {
-    /// Pool the this user belongs to.
-    pub pool: Pubkey,
-    /// The owner of this account.
-    pub owner: Pubkey,
-    /// The amount of token A claimed.
-    pub reward_per_token_complete: u128,
-    /// The amount of token A pending claim.
-    pub reward_per_token_pending: u64,
-    /// The amount staked.
-    pub balance_staked: u64,
-    /// Maturity time.
-    pub maturity_time: u64,
-    /// User's tier
-    pub tier: u8,
-    /// Signer nonce.
-    pub nonce: u8,
-}
+use crate::constants::{MAX_REWARDS, MAX_STAKE_LOTS, TIER_COUNT};
+use anchor_lang::prelude::*;
+
+#[account]
+pub struct Pool {
+    /// Priviledged account.
+    pub authority: Pubkey,
+    /// Proposed authority, who must accept to take over the pool.
+    pub pending_authority: Pubkey,
+    /// Nonce to derive the program-derived address owning the vaults.
+    pub nonce: u8,
+    /// Paused state of the program
+    pub paused: bool,
+    /// Mint of the token that can be staked.
+    pub staking_mint: Pubkey,
+    /// Vault to store staked tokens.
+    pub staking_vault: Pubkey,
+    /// Period to lock staked token and rewards
+    pub lock_period: u64,
+    /// Number of reward streams in use.
+    pub reward_count: u8,
+    /// Reward streams distributed to stakers.
+    pub rewards: [RewardInfo; MAX_REWARDS],
+    /// Users staked
+    pub user_stake_count: u32,
+    /// Total staked amount
+    pub total_staked: u64,
+    /// Calculate tier or not
+    pub no_tier: bool,
+    /// Allow claiming rewards while staked tokens are locked
+    pub claim_before_maturity: bool,
+    /// Minimum staked amount for each tier
+    pub tier_thresholds: [u64; TIER_COUNT],
+    /// Penalty in basis points charged on early unstake, 0 disables early unstake
+    pub early_unstake_penalty: u64,
+    /// Decay the penalty linearly to zero at maturity
+    pub penalty_decay: bool,
+    /// Token account receiving penalties, default routes them to the reward vault
+    pub penalty_treasury: Pubkey,
+    /// authorized funders
+    /// [] because short size, fixed account size, and ease of use on
+    /// client due to auto generated account size property
+    pub funders: [Pubkey; 5],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct RewardInfo {
+    /// Mint of the reward token.
+    pub reward_mint: Pubkey,
+    /// Vault to store reward tokens.
+    pub reward_vault: Pubkey,
+    /// The period which rewards are linearly distributed.
+    pub reward_duration: u64,
+    /// The timestamp at which the current reward period ends.
+    pub reward_duration_end: u64,
+    /// The last time reward states were updated.
+    pub last_update_time: u64,
+    /// Rate of reward distribution.
+    pub reward_rate: u64,
+    /// Last calculated reward per pool token.
+    pub reward_per_token_stored: u128,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct StakeLot {
+    /// The amount staked in this lot.
+    pub amount: u64,
+    /// Maturity time of this lot.
+    pub maturity_time: u64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct User {
+    /// Pool the this user belongs to.
+    pub pool: Pubkey,
+    /// The owner of this account.
+    pub owner: Pubkey,
+    /// The amount of each reward token claimed.
+    pub reward_per_token_complete: [u128; MAX_REWARDS],
+    /// The amount of each reward token pending claim.
+    pub reward_per_token_pending: [u64; MAX_REWARDS],
+    /// The amount staked.
+    pub balance_staked: u64,
+    /// Maturity time of the latest lot, all stake is unlocked after it.
+    pub maturity_time: u64,
+    /// Staked amounts with their own maturity time.
+    pub stake_lots: [StakeLot; MAX_STAKE_LOTS],
+    /// User's tier
+    pub tier: u8,
+    /// Signer nonce.
+    pub nonce: u8,
+}
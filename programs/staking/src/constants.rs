@@ -1,9 +1,16 @@
-pub const TIER_INFO: [u64; 7] = [
-  2_000_000_000,   // Begineer
-  6_000_000_000,   // Soldier
-  12_000_000_000,  // Commander
-  30_000_000_000,  // Lord
-  50_000_000_000,  // King
-  100_000_000_000, // Legend
-  200_000_000_000, // Immortal
-];
+pub const MAX_REWARDS: usize = 3;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_STAKE_LOTS: usize = 8;
+
+pub const TIER_COUNT: usize = 7;
+
+/// Default tier thresholds assigned to new pools.
+pub const TIER_INFO: [u64; TIER_COUNT] = [
+  2_000_000_000,   // Begineer
+  6_000_000_000,   // Soldier
+  12_000_000_000,  // Commander
+  30_000_000_000,  // Lord
+  50_000_000_000,  // King
+  100_000_000_000, // Legend
+  200_000_000_000, // Immortal
+];
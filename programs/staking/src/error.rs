@@ -1,23 +1,47 @@
-use anchor_lang::prelude::*;
-
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Insufficient funds to unstake.")]
-    InsufficientFundUnstake,
-    #[msg("Amount must be greater than zero.")]
-    AmountMustBeGreaterThanZero,
-    #[msg("Pool is paused.")]
-    PoolPaused,
-    #[msg("Duration cannot be shorter than one day.")]
-    DurationTooShort,
-    #[msg("Provided funder is already authorized to fund.")]
-    FunderAlreadyAuthorized,
-    #[msg("Maximum funders already authorized.")]
-    MaxFunders,
-    #[msg("Cannot deauthorize the primary pool authority.")]
-    CannotDeauthorizePoolAuthority,
-    #[msg("Authority not found for deauthorization.")]
-    CannotDeauthorizeMissingAuthority,
-    #[msg("Need to wait until maturity time to stake or claim.")]
-    CannotStakeOrClaimBeforeMaturity,
-}
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Insufficient funds to unstake.")]
+    InsufficientFundUnstake,
+    #[msg("Amount must be greater than zero.")]
+    AmountMustBeGreaterThanZero,
+    #[msg("Pool is paused.")]
+    PoolPaused,
+    #[msg("Duration cannot be shorter than one day.")]
+    DurationTooShort,
+    #[msg("Provided funder is already authorized to fund.")]
+    FunderAlreadyAuthorized,
+    #[msg("Maximum funders already authorized.")]
+    MaxFunders,
+    #[msg("Cannot deauthorize the primary pool authority.")]
+    CannotDeauthorizePoolAuthority,
+    #[msg("Authority not found for deauthorization.")]
+    CannotDeauthorizeMissingAuthority,
+    #[msg("Need to wait until maturity time to stake or claim.")]
+    CannotStakeOrClaimBeforeMaturity,
+    #[msg("Tier thresholds must be greater than zero and strictly increasing.")]
+    InvalidTierThresholds,
+    #[msg("Maximum reward tokens already added.")]
+    MaxRewards,
+    #[msg("Reward mint is already distributed by the pool.")]
+    RewardMintAlreadyAdded,
+    #[msg("Invalid reward index.")]
+    InvalidRewardIndex,
+    #[msg("Invalid reward vault.")]
+    InvalidRewardVault,
+    #[msg("Penalty cannot be greater than 100%.")]
+    InvalidPenalty,
+    #[msg("Early unstake is disabled.")]
+    EarlyUnstakeDisabled,
+    #[msg("Invalid penalty vault.")]
+    InvalidPenaltyVault,
+    #[msg("Only rewards in the staking token can be compounded.")]
+    CannotCompoundReward,
+    #[msg("Signer is not the pending authority.")]
+    NotPendingAuthority,
+    #[msg("Penalties need a treasury or a reward stream in the staking token.")]
+    InvalidPenaltyTreasury,
+    #[msg("No stakers remain to receive the penalty.")]
+    NoStakersForPenalty,
+}
@@ -0,0 +1,260 @@
+use crate::constants::TIER_COUNT;
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct Staked {
+    /// Pool the stake was added to.
+    pub pool: Pubkey,
+    /// Owner of the stake.
+    pub user: Pubkey,
+    /// Staked amount.
+    pub amount: u64,
+    /// User's staked balance after the stake.
+    pub balance_staked: u64,
+    /// Pool's total staked amount after the stake.
+    pub total_staked: u64,
+    /// User's tier before the stake.
+    pub previous_tier: u8,
+    /// User's tier after the stake.
+    pub tier: u8,
+    /// Maturity time after the stake.
+    pub maturity_time: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Unstaked {
+    /// Pool the stake was removed from.
+    pub pool: Pubkey,
+    /// Owner of the stake.
+    pub user: Pubkey,
+    /// Unstaked amount.
+    pub amount: u64,
+    /// User's staked balance after the unstake.
+    pub balance_staked: u64,
+    /// Pool's total staked amount after the unstake.
+    pub total_staked: u64,
+    /// User's tier before the unstake.
+    pub previous_tier: u8,
+    /// User's tier after the unstake.
+    pub tier: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyUnstaked {
+    /// Pool the stake was removed from.
+    pub pool: Pubkey,
+    /// Owner of the stake.
+    pub user: Pubkey,
+    /// Unstaked amount, including the penalty.
+    pub amount: u64,
+    /// Penalty charged on the unstaked amount.
+    pub penalty: u64,
+    /// Token account receiving the penalty.
+    pub penalty_vault: Pubkey,
+    /// User's staked balance after the unstake.
+    pub balance_staked: u64,
+    /// Pool's total staked amount after the unstake.
+    pub total_staked: u64,
+    /// User's tier before the unstake.
+    pub previous_tier: u8,
+    /// User's tier after the unstake.
+    pub tier: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Compounded {
+    /// Pool the rewards were compounded in.
+    pub pool: Pubkey,
+    /// Owner of the stake.
+    pub user: Pubkey,
+    /// Compounded amount.
+    pub amount: u64,
+    /// User's staked balance after compounding.
+    pub balance_staked: u64,
+    /// Pool's total staked amount after compounding.
+    pub total_staked: u64,
+    /// User's tier before compounding.
+    pub previous_tier: u8,
+    /// User's tier after compounding.
+    pub tier: u8,
+    /// Maturity time after compounding.
+    pub maturity_time: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardFunded {
+    /// Funded pool.
+    pub pool: Pubkey,
+    /// Account which funded the rewards.
+    pub funder: Pubkey,
+    /// Mint of the funded reward token.
+    pub reward_mint: Pubkey,
+    /// Funded amount.
+    pub amount: u64,
+    /// Rate of reward distribution after funding.
+    pub reward_rate: u64,
+    /// The timestamp at which the new reward period ends.
+    pub reward_duration_end: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardClaimed {
+    /// Pool the rewards were claimed from.
+    pub pool: Pubkey,
+    /// Owner of the stake.
+    pub user: Pubkey,
+    /// Mint of the claimed reward token.
+    pub reward_mint: Pubkey,
+    /// Claimed amount.
+    pub amount: u64,
+    /// User's tier after the claim.
+    pub tier: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityProposed {
+    /// Pool whose authority is transferred.
+    pub pool: Pubkey,
+    /// Current authority.
+    pub authority: Pubkey,
+    /// Proposed authority.
+    pub pending_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    /// Pool whose authority was transferred.
+    pub pool: Pubkey,
+    /// Previous authority.
+    pub previous_authority: Pubkey,
+    /// New authority.
+    pub authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TierThresholdsUpdated {
+    /// Updated pool.
+    pub pool: Pubkey,
+    /// Minimum staked amounts of each tier.
+    pub tier_thresholds: [u64; TIER_COUNT],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct RewardAdded {
+    /// Pool the reward token was added to.
+    pub pool: Pubkey,
+    /// Mint of the added reward token.
+    pub reward_mint: Pubkey,
+    /// Vault holding the reward tokens.
+    pub reward_vault: Pubkey,
+    /// Duration of each reward period.
+    pub reward_duration: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct EarlyUnstakePenaltyUpdated {
+    /// Updated pool.
+    pub pool: Pubkey,
+    /// Penalty in basis points charged on early unstake.
+    pub early_unstake_penalty: u64,
+    /// Whether the penalty decays linearly until maturity.
+    pub penalty_decay: bool,
+    /// Token account receiving penalties.
+    pub penalty_treasury: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolInitialized {
+    /// Initialized pool.
+    pub pool: Pubkey,
+    /// Pool authority.
+    pub authority: Pubkey,
+    /// Mint of the token that can be staked.
+    pub staking_mint: Pubkey,
+    /// Mint of the first reward token.
+    pub reward_mint: Pubkey,
+    /// Duration of each reward period.
+    pub reward_duration: u64,
+    /// Period to lock staked tokens.
+    pub lock_period: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserCreated {
+    /// Pool the user stakes in.
+    pub pool: Pubkey,
+    /// Owner of the stake.
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UserClosed {
+    /// Pool the user staked in.
+    pub pool: Pubkey,
+    /// Owner of the closed stake.
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolPaused {
+    /// Paused pool.
+    pub pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolUnpaused {
+    /// Unpaused pool.
+    pub pool: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PoolClosed {
+    /// Closed pool.
+    pub pool: Pubkey,
+    /// Account receiving the pool rent.
+    pub refundee: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FunderAuthorized {
+    /// Updated pool.
+    pub pool: Pubkey,
+    /// Authorized funder.
+    pub funder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FunderDeauthorized {
+    /// Updated pool.
+    pub pool: Pubkey,
+    /// Deauthorized funder.
+    pub funder: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ClaimBeforeMaturityUpdated {
+    /// Updated pool.
+    pub pool: Pubkey,
+    /// Whether rewards can be claimed while staked tokens are locked.
+    pub claim_before_maturity: bool,
+    pub timestamp: i64,
+}
@@ -1,491 +1,1039 @@
-pub mod account;
-pub mod constants;
-pub mod context;
-pub mod error;
-pub mod utils;
-
-use account::*;
-use anchor_lang::prelude::*;
-use anchor_lang::solana_program::clock;
-use anchor_spl::token::{self};
-use context::*;
-use error::ErrorCode;
-use std::convert::Into;
-use std::convert::TryFrom;
-use std::convert::TryInto;
-use utils::*;
-
-declare_id!("4q9ZkdHGrUy9jiaYLCYhwPP64K67Y64mhwYS1yKJaXCE");
-
-pub const PRECISION: u128 = u64::MAX as u128;
-pub const MIN_DURATION: u64 = 86400;
-
-pub fn update_rewards(
-    pool: &mut Account<Pool>,
-    user: Option<&mut Box<Account<User>>>,
-    total_staked: u64,
-) -> Result<()> {
-    let clock = clock::Clock::get().unwrap();
-    let last_time_reward_applicable =
-        last_time_reward_applicable(pool.reward_duration_end, clock.unix_timestamp);
-
-    pool.reward_per_token_stored = reward_per_token(
-        total_staked,
-        pool.reward_per_token_stored,
-        last_time_reward_applicable,
-        pool.last_update_time,
-        pool.reward_rate,
-    );
-
-    pool.last_update_time = last_time_reward_applicable;
-
-    if let Some(u) = user {
-        u.reward_per_token_pending = earned(
-            u.balance_staked,
-            pool.reward_per_token_stored,
-            u.reward_per_token_complete,
-            u.reward_per_token_pending,
-        );
-        u.reward_per_token_complete = pool.reward_per_token_stored;
-    }
-    Ok(())
-}
-
-pub fn last_time_reward_applicable(reward_duration_end: u64, unix_timestamp: i64) -> u64 {
-    return std::cmp::min(unix_timestamp.try_into().unwrap(), reward_duration_end);
-}
-
-pub fn reward_per_token(
-    total_staked: u64,
-    reward_per_token_stored: u128,
-    last_time_reward_applicable: u64,
-    last_update_time: u64,
-    reward_rate: u64,
-) -> u128 {
-    if total_staked == 0 {
-        return reward_per_token_stored;
-    }
-
-    return reward_per_token_stored
-        .checked_add(
-            (last_time_reward_applicable as u128)
-                .checked_sub(last_update_time as u128)
-                .unwrap()
-                .checked_mul(reward_rate as u128)
-                .unwrap()
-                .checked_mul(PRECISION)
-                .unwrap()
-                .checked_div(total_staked as u128)
-                .unwrap(),
-        )
-        .unwrap();
-}
-
-pub fn earned(
-    balance_staked: u64,
-    reward_per_token: u128,
-    user_reward_per_token_paid: u128,
-    user_reward_pending: u64,
-) -> u64 {
-    return (balance_staked as u128)
-        .checked_mul(
-            (reward_per_token as u128)
-                .checked_sub(user_reward_per_token_paid as u128)
-                .unwrap(),
-        )
-        .unwrap()
-        .checked_div(PRECISION)
-        .unwrap()
-        .checked_add(user_reward_pending as u128)
-        .unwrap()
-        .try_into()
-        .unwrap();
-}
-
-#[program]
-pub mod staking {
-    use super::*;
-
-    pub fn initialize_pool(
-        ctx: Context<InitializePool>,
-        pool_nonce: u8,
-        reward_duration: u64,
-        lock_period: u64,
-        no_tier: bool,
-    ) -> Result<()> {
-        if reward_duration < MIN_DURATION {
-            return Err(ErrorCode::DurationTooShort.into());
-        }
-
-        let pool = &mut ctx.accounts.pool;
-
-        pool.authority = ctx.accounts.authority.key();
-        pool.nonce = pool_nonce;
-        pool.paused = false;
-        pool.staking_mint = ctx.accounts.staking_mint.key();
-        pool.staking_vault = ctx.accounts.staking_vault.key();
-        pool.reward_mint = ctx.accounts.reward_mint.key();
-        pool.reward_vault = ctx.accounts.reward_vault.key();
-        pool.reward_duration = reward_duration;
-        pool.reward_duration_end = 0;
-        pool.lock_period = lock_period;
-        pool.last_update_time = 0;
-        pool.reward_rate = 0;
-        pool.reward_per_token_stored = 0;
-        pool.user_stake_count = 0;
-        pool.total_staked = 0;
-        pool.no_tier = no_tier;
-
-        Ok(())
-    }
-
-    pub fn create_user(ctx: Context<CreateUser>) -> Result<()> {
-        let user = &mut ctx.accounts.user;
-        user.pool = *ctx.accounts.pool.to_account_info().key;
-        user.owner = *ctx.accounts.owner.key;
-        user.reward_per_token_complete = 0;
-        user.reward_per_token_pending = 0;
-        user.balance_staked = 0;
-        user.maturity_time = 0;
-        user.tier = 0;
-        user.nonce = *ctx.bumps.get("user").unwrap();
-
-        let pool = &mut ctx.accounts.pool;
-        pool.user_stake_count = pool.user_stake_count.checked_add(1).unwrap();
-
-        Ok(())
-    }
-
-    pub fn pause(ctx: Context<Pause>) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        pool.paused = true;
-
-        Ok(())
-    }
-
-    pub fn unpause(ctx: Context<Unpause>) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        pool.paused = false;
-        Ok(())
-    }
-
-    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
-        if amount == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-
-        let pool = &mut ctx.accounts.pool;
-        if pool.paused {
-            return Err(ErrorCode::PoolPaused.into());
-        }
-
-        let total_staked = pool.total_staked;
-
-        let user_opt = Some(&mut ctx.accounts.user);
-        update_rewards(pool, user_opt, total_staked).unwrap();
-        let clock = clock::Clock::get().unwrap();
-        ctx.accounts.user.balance_staked = ctx
-            .accounts
-            .user
-            .balance_staked
-            .checked_add(amount)
-            .unwrap();
-        ctx.accounts.user.maturity_time = u64::try_from(clock.unix_timestamp)
-            .unwrap()
-            .checked_add(pool.lock_period)
-            .unwrap();
-
-        if pool.no_tier == false {
-            ctx.accounts.user.tier = get_tier(ctx.accounts.user.balance_staked);
-        }
-
-        // Transfer tokens into the stake vault.
-        {
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.stake_from_account.to_account_info(),
-                    to: ctx.accounts.staking_vault.to_account_info(),
-                    authority: ctx.accounts.owner.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, amount)?;
-        }
-
-        pool.total_staked += amount;
-
-        Ok(())
-    }
-
-    pub fn unstake(ctx: Context<Stake>, spt_amount: u64) -> Result<()> {
-        if spt_amount == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-
-        let clock = clock::Clock::get().unwrap();
-        if ctx.accounts.user.maturity_time > u64::try_from(clock.unix_timestamp).unwrap() {
-            return Err(ErrorCode::CannotStakeOrClaimBeforeMaturity.into());
-        }
-
-        if ctx.accounts.user.balance_staked < spt_amount {
-            return Err(ErrorCode::InsufficientFundUnstake.into());
-        }
-
-        let pool = &mut ctx.accounts.pool;
-        let total_staked = pool.total_staked;
-
-        let user_opt = Some(&mut ctx.accounts.user);
-        update_rewards(pool, user_opt, total_staked).unwrap();
-        ctx.accounts.user.balance_staked = ctx
-            .accounts
-            .user
-            .balance_staked
-            .checked_sub(spt_amount)
-            .unwrap();
-
-        if pool.no_tier == false {
-            ctx.accounts.user.tier = get_tier(ctx.accounts.user.balance_staked);
-        }
-
-        pool.total_staked -= spt_amount;
-
-        // Transfer tokens from the pool vault to user vault.
-        {
-            let seeds = &[pool.to_account_info().key.as_ref(), &[pool.nonce]];
-            let pool_signer = &[&seeds[..]];
-
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.staking_vault.to_account_info(),
-                    to: ctx.accounts.stake_from_account.to_account_info(),
-                    authority: ctx.accounts.pool_signer.to_account_info(),
-                },
-                pool_signer,
-            );
-            token::transfer(cpi_ctx, spt_amount.try_into().unwrap())?;
-        }
-
-        Ok(())
-    }
-
-    pub fn authorize_funder(ctx: Context<FunderChange>, funder_to_add: Pubkey) -> Result<()> {
-        if funder_to_add == ctx.accounts.pool.authority {
-            return Err(ErrorCode::FunderAlreadyAuthorized.into());
-        }
-        let funders = &mut ctx.accounts.pool.funders;
-        if funders.iter().any(|x| *x == funder_to_add) {
-            return Err(ErrorCode::FunderAlreadyAuthorized.into());
-        }
-        let default_pubkey = Pubkey::default();
-        if let Some(idx) = funders.iter().position(|x| *x == default_pubkey) {
-            funders[idx] = funder_to_add;
-        } else {
-            return Err(ErrorCode::MaxFunders.into());
-        }
-        Ok(())
-    }
-
-    pub fn deauthorize_funder(ctx: Context<FunderChange>, funder_to_remove: Pubkey) -> Result<()> {
-        if funder_to_remove == ctx.accounts.pool.authority {
-            return Err(ErrorCode::CannotDeauthorizePoolAuthority.into());
-        }
-        let funders = &mut ctx.accounts.pool.funders;
-        if let Some(idx) = funders.iter().position(|x| *x == funder_to_remove) {
-            funders[idx] = Pubkey::default();
-        } else {
-            return Err(ErrorCode::CannotDeauthorizeMissingAuthority.into());
-        }
-        Ok(())
-    }
-
-    pub fn fund(ctx: Context<Fund>, amount: u64) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        let total_staked = pool.total_staked;
-
-        update_rewards(pool, None, total_staked).unwrap();
-
-        let current_time = clock::Clock::get()
-            .unwrap()
-            .unix_timestamp
-            .try_into()
-            .unwrap();
-        let reward_period_end = pool.reward_duration_end;
-
-        if current_time >= reward_period_end {
-            pool.reward_rate = amount.checked_div(pool.reward_duration).unwrap();
-        } else {
-            let remaining = pool.reward_duration_end.checked_sub(current_time).unwrap();
-            let leftover = remaining.checked_mul(pool.reward_rate).unwrap();
-
-            pool.reward_rate = amount
-                .checked_add(leftover)
-                .unwrap()
-                .checked_div(pool.reward_duration)
-                .unwrap();
-        }
-
-        // Transfer reward A tokens into the A vault.
-        if amount > 0 {
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.from.to_account_info(),
-                    to: ctx.accounts.reward_vault.to_account_info(),
-                    authority: ctx.accounts.funder.to_account_info(),
-                },
-            );
-
-            token::transfer(cpi_ctx, amount)?;
-        }
-
-        pool.last_update_time = current_time;
-        pool.reward_duration_end = current_time.checked_add(pool.reward_duration).unwrap();
-
-        Ok(())
-    }
-
-    pub fn claim(ctx: Context<ClaimReward>) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        let total_staked = pool.total_staked;
-
-        let clock = clock::Clock::get().unwrap();
-        if ctx.accounts.user.maturity_time > u64::try_from(clock.unix_timestamp).unwrap() {
-            return Err(ErrorCode::CannotStakeOrClaimBeforeMaturity.into());
-        }
-
-        let user_opt = Some(&mut ctx.accounts.user);
-        update_rewards(pool, user_opt, total_staked).unwrap();
-
-        let seeds = &[pool.to_account_info().key.as_ref(), &[pool.nonce]];
-        let pool_signer = &[&seeds[..]];
-
-        if ctx.accounts.user.reward_per_token_pending > 0 {
-            let mut reward_amount = ctx.accounts.user.reward_per_token_pending;
-            let vault_balance = ctx.accounts.reward_vault.amount;
-
-            ctx.accounts.user.reward_per_token_pending = 0;
-            if vault_balance < reward_amount {
-                reward_amount = vault_balance;
-            }
-
-            if reward_amount > 0 {
-                let cpi_ctx = CpiContext::new_with_signer(
-                    ctx.accounts.token_program.to_account_info(),
-                    token::Transfer {
-                        from: ctx.accounts.reward_vault.to_account_info(),
-                        to: ctx.accounts.reward_account.to_account_info(),
-                        authority: ctx.accounts.pool_signer.to_account_info(),
-                    },
-                    pool_signer,
-                );
-                token::transfer(cpi_ctx, reward_amount)?;
-            }
-        }
-        Ok(())
-    }
-
-    pub fn close_user(ctx: Context<CloseUser>) -> Result<()> {
-        let pool = &mut ctx.accounts.pool;
-        pool.user_stake_count = pool.user_stake_count.checked_sub(1).unwrap();
-        Ok(())
-    }
-
-    pub fn close_pool<'info>(ctx: Context<ClosePool>) -> Result<()> {
-        let pool = &ctx.accounts.pool;
-
-        let signer_seeds = &[
-            pool.to_account_info().key.as_ref(),
-            &[ctx.accounts.pool.nonce],
-        ];
-
-        //instead of closing these vaults, we could technically just
-        //set_authority on them. it's not very ata clean, but it'd work
-        //if size of tx is an issue, thats an approach
-
-        //close staking vault
-        let staking_vault_balance = ctx.accounts.staking_vault.amount;
-
-        if staking_vault_balance > 0 {
-            let ix = spl_token::instruction::transfer(
-                &spl_token::ID,
-                ctx.accounts.staking_vault.to_account_info().key,
-                ctx.accounts.staking_refundee.to_account_info().key,
-                ctx.accounts.pool_signer.key,
-                &[ctx.accounts.pool_signer.key],
-                staking_vault_balance,
-            )?;
-            solana_program::program::invoke_signed(
-                &ix,
-                &[
-                    ctx.accounts.token_program.to_account_info(),
-                    ctx.accounts.staking_vault.to_account_info(),
-                    ctx.accounts.staking_refundee.to_account_info(),
-                    ctx.accounts.pool_signer.to_account_info(),
-                ],
-                &[signer_seeds],
-            )?;
-        }
-
-        let ix = spl_token::instruction::close_account(
-            &spl_token::ID,
-            ctx.accounts.staking_vault.to_account_info().key,
-            ctx.accounts.refundee.key,
-            ctx.accounts.pool_signer.key,
-            &[ctx.accounts.pool_signer.key],
-        )?;
-        solana_program::program::invoke_signed(
-            &ix,
-            &[
-                ctx.accounts.token_program.to_account_info(),
-                ctx.accounts.staking_vault.to_account_info(),
-                ctx.accounts.refundee.to_account_info(),
-                ctx.accounts.pool_signer.to_account_info(),
-            ],
-            &[signer_seeds],
-        )?;
-
-        //close token a vault
-        let reward_vault_balance = ctx.accounts.reward_vault.amount;
-
-        if reward_vault_balance > 0 {
-            let ix = spl_token::instruction::transfer(
-                &spl_token::ID,
-                ctx.accounts.reward_vault.to_account_info().key,
-                ctx.accounts.reward_refundee.to_account_info().key,
-                ctx.accounts.pool_signer.key,
-                &[ctx.accounts.pool_signer.key],
-                reward_vault_balance,
-            )?;
-            solana_program::program::invoke_signed(
-                &ix,
-                &[
-                    ctx.accounts.token_program.to_account_info(),
-                    ctx.accounts.reward_vault.to_account_info(),
-                    ctx.accounts.reward_refundee.to_account_info(),
-                    ctx.accounts.pool_signer.to_account_info(),
-                ],
-                &[signer_seeds],
-            )?;
-        }
-        let ix = spl_token::instruction::close_account(
-            &spl_token::ID,
-            ctx.accounts.reward_vault.to_account_info().key,
-            ctx.accounts.refundee.key,
-            ctx.accounts.pool_signer.key,
-            &[ctx.accounts.pool_signer.key],
-        )?;
-        solana_program::program::invoke_signed(
-            &ix,
-            &[
-                ctx.accounts.token_program.to_account_info(),
-                ctx.accounts.reward_vault.to_account_info(),
-                ctx.accounts.refundee.to_account_info(),
-                ctx.accounts.pool_signer.to_account_info(),
-            ],
-            &[signer_seeds],
-        )?;
-
-        Ok(())
-    }
-}
+pub mod account;
+pub mod constants;
+pub mod context;
+pub mod error;
+pub mod event;
+pub mod utils;
+
+use account::*;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::clock;
+use anchor_spl::token::{self, TokenAccount};
+use constants::*;
+use context::*;
+use error::ErrorCode;
+use event::*;
+use std::convert::Into;
+use std::convert::TryFrom;
+use std::convert::TryInto;
+use utils::*;
+
+declare_id!("4q9ZkdHGrUy9jiaYLCYhwPP64K67Y64mhwYS1yKJaXCE");
+
+pub const PRECISION: u128 = u64::MAX as u128;
+pub const MIN_DURATION: u64 = 86400;
+
+pub fn update_rewards(
+    pool: &mut Account<Pool>,
+    user: Option<&mut Box<Account<User>>>,
+    total_staked: u64,
+) -> Result<()> {
+    let clock = clock::Clock::get().unwrap();
+    let mut user = user;
+
+    for i in 0..pool.reward_count as usize {
+        let reward = &mut pool.rewards[i];
+        let last_time_reward_applicable =
+            last_time_reward_applicable(reward.reward_duration_end, clock.unix_timestamp);
+
+        reward.reward_per_token_stored = reward_per_token(
+            total_staked,
+            reward.reward_per_token_stored,
+            last_time_reward_applicable,
+            reward.last_update_time,
+            reward.reward_rate,
+        );
+
+        reward.last_update_time = last_time_reward_applicable;
+
+        if let Some(u) = user.as_mut() {
+            u.reward_per_token_pending[i] = earned(
+                u.balance_staked,
+                reward.reward_per_token_stored,
+                u.reward_per_token_complete[i],
+                u.reward_per_token_pending[i],
+            );
+            u.reward_per_token_complete[i] = reward.reward_per_token_stored;
+        }
+    }
+    Ok(())
+}
+
+pub fn last_time_reward_applicable(reward_duration_end: u64, unix_timestamp: i64) -> u64 {
+    return std::cmp::min(unix_timestamp.try_into().unwrap(), reward_duration_end);
+}
+
+pub fn reward_per_token(
+    total_staked: u64,
+    reward_per_token_stored: u128,
+    last_time_reward_applicable: u64,
+    last_update_time: u64,
+    reward_rate: u64,
+) -> u128 {
+    if total_staked == 0 {
+        return reward_per_token_stored;
+    }
+
+    return reward_per_token_stored
+        .checked_add(
+            (last_time_reward_applicable as u128)
+                .checked_sub(last_update_time as u128)
+                .unwrap()
+                .checked_mul(reward_rate as u128)
+                .unwrap()
+                .checked_mul(PRECISION)
+                .unwrap()
+                .checked_div(total_staked as u128)
+                .unwrap(),
+        )
+        .unwrap();
+}
+
+pub fn earned(
+    balance_staked: u64,
+    reward_per_token: u128,
+    user_reward_per_token_paid: u128,
+    user_reward_pending: u64,
+) -> u64 {
+    return (balance_staked as u128)
+        .checked_mul(
+            (reward_per_token as u128)
+                .checked_sub(user_reward_per_token_paid as u128)
+                .unwrap(),
+        )
+        .unwrap()
+        .checked_div(PRECISION)
+        .unwrap()
+        .checked_add(user_reward_pending as u128)
+        .unwrap()
+        .try_into()
+        .unwrap();
+}
+
+pub fn notify_reward_amount(reward: &mut RewardInfo, amount: u64, current_time: u64) {
+    let reward_period_end = reward.reward_duration_end;
+
+    if current_time >= reward_period_end {
+        reward.reward_rate = amount.checked_div(reward.reward_duration).unwrap();
+    } else {
+        let remaining = reward
+            .reward_duration_end
+            .checked_sub(current_time)
+            .unwrap();
+        let leftover = remaining.checked_mul(reward.reward_rate).unwrap();
+
+        reward.reward_rate = amount
+            .checked_add(leftover)
+            .unwrap()
+            .checked_div(reward.reward_duration)
+            .unwrap();
+    }
+
+    reward.last_update_time = current_time;
+    reward.reward_duration_end = current_time.checked_add(reward.reward_duration).unwrap();
+}
+
+/// Pays `amount` to current stakers at once, leaving the reward rate and
+/// period untouched so it cannot delay the stream.
+pub fn distribute_reward(reward: &mut RewardInfo, amount: u64, total_staked: u64) {
+    reward.reward_per_token_stored = reward
+        .reward_per_token_stored
+        .checked_add(
+            (amount as u128)
+                .checked_mul(PRECISION)
+                .unwrap()
+                .checked_div(total_staked as u128)
+                .unwrap(),
+        )
+        .unwrap();
+}
+
+pub fn close_vault<'info>(
+    token_program: &AccountInfo<'info>,
+    vault: &AccountInfo<'info>,
+    vault_balance: u64,
+    vault_refundee: &AccountInfo<'info>,
+    refundee: &AccountInfo<'info>,
+    pool_signer: &AccountInfo<'info>,
+    signer_seeds: &[&[u8]],
+) -> Result<()> {
+    if vault_balance > 0 {
+        let ix = spl_token::instruction::transfer(
+            &spl_token::ID,
+            vault.key,
+            vault_refundee.key,
+            pool_signer.key,
+            &[pool_signer.key],
+            vault_balance,
+        )?;
+        solana_program::program::invoke_signed(
+            &ix,
+            &[
+                token_program.clone(),
+                vault.clone(),
+                vault_refundee.clone(),
+                pool_signer.clone(),
+            ],
+            &[signer_seeds],
+        )?;
+    }
+
+    let ix = spl_token::instruction::close_account(
+        &spl_token::ID,
+        vault.key,
+        refundee.key,
+        pool_signer.key,
+        &[pool_signer.key],
+    )?;
+    solana_program::program::invoke_signed(
+        &ix,
+        &[
+            token_program.clone(),
+            vault.clone(),
+            refundee.clone(),
+            pool_signer.clone(),
+        ],
+        &[signer_seeds],
+    )?;
+    Ok(())
+}
+
+#[program]
+pub mod staking {
+    use super::*;
+
+    pub fn initialize_pool(
+        ctx: Context<InitializePool>,
+        pool_nonce: u8,
+        reward_duration: u64,
+        lock_period: u64,
+        no_tier: bool,
+        claim_before_maturity: bool,
+    ) -> Result<()> {
+        if reward_duration < MIN_DURATION {
+            return Err(ErrorCode::DurationTooShort.into());
+        }
+
+        let pool = &mut ctx.accounts.pool;
+
+        pool.authority = ctx.accounts.authority.key();
+        pool.pending_authority = Pubkey::default();
+        pool.nonce = pool_nonce;
+        pool.paused = false;
+        pool.staking_mint = ctx.accounts.staking_mint.key();
+        pool.staking_vault = ctx.accounts.staking_vault.key();
+        pool.lock_period = lock_period;
+        pool.reward_count = 1;
+        pool.rewards[0] = RewardInfo {
+            reward_mint: ctx.accounts.reward_mint.key(),
+            reward_vault: ctx.accounts.reward_vault.key(),
+            reward_duration,
+            reward_duration_end: 0,
+            last_update_time: 0,
+            reward_rate: 0,
+            reward_per_token_stored: 0,
+        };
+        pool.user_stake_count = 0;
+        pool.total_staked = 0;
+        pool.no_tier = no_tier;
+        pool.claim_before_maturity = claim_before_maturity;
+        pool.tier_thresholds = TIER_INFO;
+        pool.early_unstake_penalty = 0;
+        pool.penalty_decay = false;
+        pool.penalty_treasury = Pubkey::default();
+
+        emit!(PoolInitialized {
+            pool: pool.key(),
+            authority: pool.authority,
+            staking_mint: pool.staking_mint,
+            reward_mint: pool.rewards[0].reward_mint,
+            reward_duration,
+            lock_period,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_user(ctx: Context<CreateUser>) -> Result<()> {
+        let user = &mut ctx.accounts.user;
+        user.pool = *ctx.accounts.pool.to_account_info().key;
+        user.owner = *ctx.accounts.owner.key;
+        user.reward_per_token_complete = [0; MAX_REWARDS];
+        user.reward_per_token_pending = [0; MAX_REWARDS];
+        user.balance_staked = 0;
+        user.maturity_time = 0;
+        user.stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+        user.tier = 0;
+        user.nonce = *ctx.bumps.get("user").unwrap();
+
+        let pool = &mut ctx.accounts.pool;
+        pool.user_stake_count = pool.user_stake_count.checked_add(1).unwrap();
+
+        emit!(UserCreated {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn pause(ctx: Context<Pause>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.paused = true;
+
+        emit!(PoolPaused {
+            pool: pool.key(),
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn unpause(ctx: Context<Unpause>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.paused = false;
+
+        emit!(PoolUnpaused {
+            pool: pool.key(),
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn update_tier_thresholds(
+        ctx: Context<UpdateTierThresholds>,
+        tier_thresholds: [u64; TIER_COUNT],
+    ) -> Result<()> {
+        if !is_valid_tier_thresholds(&tier_thresholds) {
+            return Err(ErrorCode::InvalidTierThresholds.into());
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        pool.tier_thresholds = tier_thresholds;
+
+        emit!(TierThresholdsUpdated {
+            pool: pool.key(),
+            tier_thresholds,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn stake(ctx: Context<Stake>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        if pool.paused {
+            return Err(ErrorCode::PoolPaused.into());
+        }
+
+        let total_staked = pool.total_staked;
+
+        let user_opt = Some(&mut ctx.accounts.user);
+        update_rewards(pool, user_opt, total_staked).unwrap();
+        let clock = clock::Clock::get().unwrap();
+        let previous_tier = ctx.accounts.user.tier;
+        ctx.accounts.user.balance_staked = ctx
+            .accounts
+            .user
+            .balance_staked
+            .checked_add(amount)
+            .unwrap();
+        let current_time = u64::try_from(clock.unix_timestamp).unwrap();
+        let user = &mut ctx.accounts.user;
+        add_stake_lot(
+            &mut user.stake_lots,
+            amount,
+            current_time.checked_add(pool.lock_period).unwrap(),
+            current_time,
+        );
+        user.maturity_time = latest_maturity_time(&user.stake_lots);
+
+        if pool.no_tier == false {
+            ctx.accounts.user.tier =
+                get_tier(&pool.tier_thresholds, ctx.accounts.user.balance_staked);
+        }
+
+        // Transfer tokens into the stake vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.stake_from_account.to_account_info(),
+                    to: ctx.accounts.staking_vault.to_account_info(),
+                    authority: ctx.accounts.owner.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        pool.total_staked += amount;
+
+        emit!(Staked {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            amount,
+            balance_staked: ctx.accounts.user.balance_staked,
+            total_staked: pool.total_staked,
+            previous_tier,
+            tier: ctx.accounts.user.tier,
+            maturity_time: ctx.accounts.user.maturity_time,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn unstake(ctx: Context<Stake>, spt_amount: u64) -> Result<()> {
+        if spt_amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+
+        if ctx.accounts.user.balance_staked < spt_amount {
+            return Err(ErrorCode::InsufficientFundUnstake.into());
+        }
+
+        let clock = clock::Clock::get().unwrap();
+        let current_time = u64::try_from(clock.unix_timestamp).unwrap();
+        if unlocked_amount(&ctx.accounts.user.stake_lots, current_time) < spt_amount {
+            return Err(ErrorCode::CannotStakeOrClaimBeforeMaturity.into());
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        let total_staked = pool.total_staked;
+
+        let user_opt = Some(&mut ctx.accounts.user);
+        update_rewards(pool, user_opt, total_staked).unwrap();
+        let previous_tier = ctx.accounts.user.tier;
+        ctx.accounts.user.balance_staked = ctx
+            .accounts
+            .user
+            .balance_staked
+            .checked_sub(spt_amount)
+            .unwrap();
+        let user = &mut ctx.accounts.user;
+        release_stake_lots(&mut user.stake_lots, spt_amount, |_, _| {});
+        user.maturity_time = latest_maturity_time(&user.stake_lots);
+
+        if !pool.no_tier {
+            ctx.accounts.user.tier =
+                get_tier(&pool.tier_thresholds, ctx.accounts.user.balance_staked);
+        }
+
+        pool.total_staked -= spt_amount;
+
+        // Transfer tokens from the pool vault to user vault.
+        {
+            let seeds = &[pool.to_account_info().key.as_ref(), &[pool.nonce]];
+            let pool_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.staking_vault.to_account_info(),
+                    to: ctx.accounts.stake_from_account.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                pool_signer,
+            );
+            token::transfer(cpi_ctx, spt_amount.try_into().unwrap())?;
+        }
+
+        emit!(Unstaked {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            amount: spt_amount,
+            balance_staked: ctx.accounts.user.balance_staked,
+            total_staked: pool.total_staked,
+            previous_tier,
+            tier: ctx.accounts.user.tier,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn early_unstake(ctx: Context<EarlyUnstake>, spt_amount: u64) -> Result<()> {
+        if spt_amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+
+        if ctx.accounts.user.balance_staked < spt_amount {
+            return Err(ErrorCode::InsufficientFundUnstake.into());
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        if pool.early_unstake_penalty == 0 {
+            return Err(ErrorCode::EarlyUnstakeDisabled.into());
+        }
+
+        // Penalties go to the treasury when set, otherwise they are paid to
+        // the remaining stakers through the reward stream of the staking token.
+        let penalty_vault = ctx.accounts.penalty_vault.key();
+        let penalty_reward_index = if pool.penalty_treasury != Pubkey::default() {
+            if penalty_vault != pool.penalty_treasury {
+                return Err(ErrorCode::InvalidPenaltyVault.into());
+            }
+            None
+        } else {
+            let idx = pool.rewards[..pool.reward_count as usize]
+                .iter()
+                .position(|x| x.reward_vault == penalty_vault && x.reward_mint == pool.staking_mint)
+                .ok_or(ErrorCode::InvalidPenaltyVault)?;
+            Some(idx)
+        };
+
+        let total_staked = pool.total_staked;
+
+        let user_opt = Some(&mut ctx.accounts.user);
+        update_rewards(pool, user_opt, total_staked).unwrap();
+
+        let clock = clock::Clock::get().unwrap();
+        let current_time = u64::try_from(clock.unix_timestamp).unwrap();
+        let mut penalty: u64 = 0;
+        let user = &mut ctx.accounts.user;
+        release_stake_lots(&mut user.stake_lots, spt_amount, |amount, maturity_time| {
+            penalty += early_unstake_penalty(
+                amount,
+                pool.early_unstake_penalty,
+                pool.penalty_decay,
+                maturity_time,
+                pool.lock_period,
+                current_time,
+            );
+        });
+        user.maturity_time = latest_maturity_time(&user.stake_lots);
+
+        let previous_tier = ctx.accounts.user.tier;
+        ctx.accounts.user.balance_staked = ctx
+            .accounts
+            .user
+            .balance_staked
+            .checked_sub(spt_amount)
+            .unwrap();
+
+        if !pool.no_tier {
+            ctx.accounts.user.tier =
+                get_tier(&pool.tier_thresholds, ctx.accounts.user.balance_staked);
+        }
+
+        pool.total_staked -= spt_amount;
+
+        if let Some(idx) = penalty_reward_index {
+            if penalty > 0 {
+                if pool.total_staked == 0 {
+                    return Err(ErrorCode::NoStakersForPenalty.into());
+                }
+                let total_staked = pool.total_staked;
+                distribute_reward(&mut pool.rewards[idx], penalty, total_staked);
+            }
+        }
+
+        // Transfer tokens from the pool vault to user vault and penalty vault.
+        {
+            let seeds = &[pool.to_account_info().key.as_ref(), &[pool.nonce]];
+            let pool_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.staking_vault.to_account_info(),
+                    to: ctx.accounts.stake_from_account.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                pool_signer,
+            );
+            token::transfer(cpi_ctx, spt_amount - penalty)?;
+
+            if penalty > 0 {
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.staking_vault.to_account_info(),
+                        to: ctx.accounts.penalty_vault.to_account_info(),
+                        authority: ctx.accounts.pool_signer.to_account_info(),
+                    },
+                    pool_signer,
+                );
+                token::transfer(cpi_ctx, penalty)?;
+            }
+        }
+
+        emit!(EarlyUnstaked {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            amount: spt_amount,
+            penalty,
+            penalty_vault,
+            balance_staked: ctx.accounts.user.balance_staked,
+            total_staked: pool.total_staked,
+            previous_tier,
+            tier: ctx.accounts.user.tier,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_early_unstake_penalty(
+        ctx: Context<SetEarlyUnstakePenalty>,
+        early_unstake_penalty: u64,
+        penalty_decay: bool,
+        penalty_treasury: Pubkey,
+    ) -> Result<()> {
+        if early_unstake_penalty > BPS_DENOMINATOR {
+            return Err(ErrorCode::InvalidPenalty.into());
+        }
+
+        let pool = &mut ctx.accounts.pool;
+        if early_unstake_penalty > 0
+            && penalty_treasury == Pubkey::default()
+            && !pool.rewards[..pool.reward_count as usize]
+                .iter()
+                .any(|x| x.reward_mint == pool.staking_mint)
+        {
+            return Err(ErrorCode::InvalidPenaltyTreasury.into());
+        }
+
+        pool.early_unstake_penalty = early_unstake_penalty;
+        pool.penalty_decay = penalty_decay;
+        pool.penalty_treasury = penalty_treasury;
+
+        emit!(EarlyUnstakePenaltyUpdated {
+            pool: pool.key(),
+            early_unstake_penalty,
+            penalty_decay,
+            penalty_treasury,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_claim_before_maturity(
+        ctx: Context<SetClaimBeforeMaturity>,
+        claim_before_maturity: bool,
+    ) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.claim_before_maturity = claim_before_maturity;
+
+        emit!(ClaimBeforeMaturityUpdated {
+            pool: pool.key(),
+            claim_before_maturity,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_authority(ctx: Context<ProposeAuthority>, new_authority: Pubkey) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.pending_authority = new_authority;
+
+        emit!(AuthorityProposed {
+            pool: pool.key(),
+            authority: pool.authority,
+            pending_authority: new_authority,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let previous_authority = pool.authority;
+        pool.authority = pool.pending_authority;
+        pool.pending_authority = Pubkey::default();
+
+        // The new authority can fund without being listed as a funder.
+        let authority = pool.authority;
+        if let Some(idx) = pool.funders.iter().position(|x| *x == authority) {
+            pool.funders[idx] = Pubkey::default();
+        }
+
+        emit!(AuthorityTransferred {
+            pool: pool.key(),
+            previous_authority,
+            authority: pool.authority,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn authorize_funder(ctx: Context<FunderChange>, funder_to_add: Pubkey) -> Result<()> {
+        if funder_to_add == ctx.accounts.pool.authority {
+            return Err(ErrorCode::FunderAlreadyAuthorized.into());
+        }
+        let funders = &mut ctx.accounts.pool.funders;
+        if funders.iter().any(|x| *x == funder_to_add) {
+            return Err(ErrorCode::FunderAlreadyAuthorized.into());
+        }
+        let default_pubkey = Pubkey::default();
+        if let Some(idx) = funders.iter().position(|x| *x == default_pubkey) {
+            funders[idx] = funder_to_add;
+        } else {
+            return Err(ErrorCode::MaxFunders.into());
+        }
+
+        emit!(FunderAuthorized {
+            pool: ctx.accounts.pool.key(),
+            funder: funder_to_add,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deauthorize_funder(ctx: Context<FunderChange>, funder_to_remove: Pubkey) -> Result<()> {
+        if funder_to_remove == ctx.accounts.pool.authority {
+            return Err(ErrorCode::CannotDeauthorizePoolAuthority.into());
+        }
+        let funders = &mut ctx.accounts.pool.funders;
+        if let Some(idx) = funders.iter().position(|x| *x == funder_to_remove) {
+            funders[idx] = Pubkey::default();
+        } else {
+            return Err(ErrorCode::CannotDeauthorizeMissingAuthority.into());
+        }
+
+        emit!(FunderDeauthorized {
+            pool: ctx.accounts.pool.key(),
+            funder: funder_to_remove,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_reward(ctx: Context<AddReward>, reward_duration: u64) -> Result<()> {
+        if reward_duration < MIN_DURATION {
+            return Err(ErrorCode::DurationTooShort.into());
+        }
+
+        let reward_mint = ctx.accounts.reward_mint.key();
+        let pool = &mut ctx.accounts.pool;
+        let reward_count = pool.reward_count as usize;
+        if reward_count >= MAX_REWARDS {
+            return Err(ErrorCode::MaxRewards.into());
+        }
+        if pool.rewards[..reward_count]
+            .iter()
+            .any(|x| x.reward_mint == reward_mint)
+        {
+            return Err(ErrorCode::RewardMintAlreadyAdded.into());
+        }
+
+        let total_staked = pool.total_staked;
+        update_rewards(pool, None, total_staked).unwrap();
+
+        pool.rewards[reward_count] = RewardInfo {
+            reward_mint,
+            reward_vault: ctx.accounts.reward_vault.key(),
+            reward_duration,
+            reward_duration_end: 0,
+            last_update_time: 0,
+            reward_rate: 0,
+            reward_per_token_stored: 0,
+        };
+        pool.reward_count += 1;
+
+        emit!(RewardAdded {
+            pool: pool.key(),
+            reward_mint,
+            reward_vault: ctx.accounts.reward_vault.key(),
+            reward_duration,
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn fund(ctx: Context<Fund>, reward_index: u8, amount: u64) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let total_staked = pool.total_staked;
+
+        update_rewards(pool, None, total_staked).unwrap();
+
+        let clock = clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+        let pool_key = pool.key();
+        let reward = &mut pool.rewards[reward_index as usize];
+        notify_reward_amount(reward, amount, current_time);
+
+        // Transfer reward tokens into the reward vault.
+        if amount > 0 {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.from.to_account_info(),
+                    to: ctx.accounts.reward_vault.to_account_info(),
+                    authority: ctx.accounts.funder.to_account_info(),
+                },
+            );
+
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(RewardFunded {
+            pool: pool_key,
+            funder: ctx.accounts.funder.key(),
+            reward_mint: reward.reward_mint,
+            amount,
+            reward_rate: reward.reward_rate,
+            reward_duration_end: reward.reward_duration_end,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn claim(ctx: Context<ClaimReward>, reward_index: u8) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let total_staked = pool.total_staked;
+
+        // Rewards accrue on every lot, so once the earliest lot has matured a
+        // later stake must not lock them again until its own maturity.
+        let clock = clock::Clock::get().unwrap();
+        if !pool.claim_before_maturity
+            && earliest_maturity_time(&ctx.accounts.user.stake_lots)
+                > u64::try_from(clock.unix_timestamp).unwrap()
+        {
+            return Err(ErrorCode::CannotStakeOrClaimBeforeMaturity.into());
+        }
+
+        let user_opt = Some(&mut ctx.accounts.user);
+        update_rewards(pool, user_opt, total_staked).unwrap();
+
+        if !pool.no_tier {
+            ctx.accounts.user.tier =
+                get_tier(&pool.tier_thresholds, ctx.accounts.user.balance_staked);
+        }
+
+        let seeds = &[pool.to_account_info().key.as_ref(), &[pool.nonce]];
+        let pool_signer = &[&seeds[..]];
+
+        let reward_index = reward_index as usize;
+        let mut reward_amount = 0;
+        if ctx.accounts.user.reward_per_token_pending[reward_index] > 0 {
+            reward_amount = ctx.accounts.user.reward_per_token_pending[reward_index];
+            let vault_balance = ctx.accounts.reward_vault.amount;
+
+            ctx.accounts.user.reward_per_token_pending[reward_index] = 0;
+            if vault_balance < reward_amount {
+                reward_amount = vault_balance;
+            }
+
+            if reward_amount > 0 {
+                let cpi_ctx = CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    token::Transfer {
+                        from: ctx.accounts.reward_vault.to_account_info(),
+                        to: ctx.accounts.reward_account.to_account_info(),
+                        authority: ctx.accounts.pool_signer.to_account_info(),
+                    },
+                    pool_signer,
+                );
+                token::transfer(cpi_ctx, reward_amount)?;
+            }
+        }
+
+        emit!(RewardClaimed {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            reward_mint: pool.rewards[reward_index].reward_mint,
+            amount: reward_amount,
+            tier: ctx.accounts.user.tier,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Compounded rewards are staked as a new lot locked for the pool's lock
+    // period; the maturity of previously staked lots is not extended.
+    pub fn compound(ctx: Context<Compound>, reward_index: u8) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        let total_staked = pool.total_staked;
+
+        let user_opt = Some(&mut ctx.accounts.user);
+        update_rewards(pool, user_opt, total_staked).unwrap();
+
+        let reward_index = reward_index as usize;
+        let mut amount = ctx.accounts.user.reward_per_token_pending[reward_index];
+        if amount > ctx.accounts.reward_vault.amount {
+            amount = ctx.accounts.reward_vault.amount;
+        }
+        if amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+        ctx.accounts.user.reward_per_token_pending[reward_index] = 0;
+
+        let clock = clock::Clock::get().unwrap();
+        let current_time = u64::try_from(clock.unix_timestamp).unwrap();
+        let previous_tier = ctx.accounts.user.tier;
+        let user = &mut ctx.accounts.user;
+        user.balance_staked = user.balance_staked.checked_add(amount).unwrap();
+        add_stake_lot(
+            &mut user.stake_lots,
+            amount,
+            current_time.checked_add(pool.lock_period).unwrap(),
+            current_time,
+        );
+        user.maturity_time = latest_maturity_time(&user.stake_lots);
+
+        if !pool.no_tier {
+            user.tier = get_tier(&pool.tier_thresholds, user.balance_staked);
+        }
+
+        // Move rewards from the reward vault into the stake vault.
+        {
+            let seeds = &[pool.to_account_info().key.as_ref(), &[pool.nonce]];
+            let pool_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.reward_vault.to_account_info(),
+                    to: ctx.accounts.staking_vault.to_account_info(),
+                    authority: ctx.accounts.pool_signer.to_account_info(),
+                },
+                pool_signer,
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        pool.total_staked += amount;
+
+        emit!(Compounded {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            amount,
+            balance_staked: ctx.accounts.user.balance_staked,
+            total_staked: pool.total_staked,
+            previous_tier,
+            tier: ctx.accounts.user.tier,
+            maturity_time: ctx.accounts.user.maturity_time,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_user(ctx: Context<CloseUser>) -> Result<()> {
+        let pool = &mut ctx.accounts.pool;
+        pool.user_stake_count = pool.user_stake_count.checked_sub(1).unwrap();
+
+        emit!(UserClosed {
+            pool: pool.key(),
+            user: ctx.accounts.owner.key(),
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn close_pool<'info>(ctx: Context<'_, '_, '_, 'info, ClosePool<'info>>) -> Result<()> {
+        let pool = &ctx.accounts.pool;
+
+        let signer_seeds = &[
+            pool.to_account_info().key.as_ref(),
+            &[ctx.accounts.pool.nonce],
+        ];
+
+        //instead of closing these vaults, we could technically just
+        //set_authority on them. it's not very ata clean, but it'd work
+        //if size of tx is an issue, thats an approach
+
+        //close staking vault
+        close_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.staking_vault.to_account_info(),
+            ctx.accounts.staking_vault.amount,
+            &ctx.accounts.staking_refundee.to_account_info(),
+            &ctx.accounts.refundee,
+            &ctx.accounts.pool_signer,
+            signer_seeds,
+        )?;
+
+        //close first reward vault
+        close_vault(
+            &ctx.accounts.token_program.to_account_info(),
+            &ctx.accounts.reward_vault.to_account_info(),
+            ctx.accounts.reward_vault.amount,
+            &ctx.accounts.reward_refundee.to_account_info(),
+            &ctx.accounts.refundee,
+            &ctx.accounts.pool_signer,
+            signer_seeds,
+        )?;
+
+        //close remaining reward vaults, passed as (vault, refundee) pairs
+        let reward_count = pool.reward_count as usize;
+        if ctx.remaining_accounts.len() != (reward_count - 1) * 2 {
+            return Err(ErrorCode::InvalidRewardVault.into());
+        }
+        for i in 1..reward_count {
+            let reward_vault = &ctx.remaining_accounts[(i - 1) * 2];
+            let reward_refundee = &ctx.remaining_accounts[(i - 1) * 2 + 1];
+            if reward_vault.key() != pool.rewards[i].reward_vault {
+                return Err(ErrorCode::InvalidRewardVault.into());
+            }
+            let reward_vault_balance = Account::<TokenAccount>::try_from(reward_vault)?.amount;
+
+            close_vault(
+                &ctx.accounts.token_program.to_account_info(),
+                reward_vault,
+                reward_vault_balance,
+                reward_refundee,
+                &ctx.accounts.refundee,
+                &ctx.accounts.pool_signer,
+                signer_seeds,
+            )?;
+        }
+
+        emit!(PoolClosed {
+            pool: pool.key(),
+            refundee: ctx.accounts.refundee.key(),
+            timestamp: clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distributed_penalty_does_not_delay_the_stream() {
+        let mut reward = RewardInfo {
+            reward_duration: 86400 * 7,
+            ..RewardInfo::default()
+        };
+        notify_reward_amount(&mut reward, 86400 * 7 * 10, 1_000);
+        let reward_duration_end = reward.reward_duration_end;
+
+        distribute_reward(&mut reward, 1, 5_000_000_000);
+
+        assert_eq!(reward.reward_duration_end, reward_duration_end);
+        assert_eq!(reward.reward_rate, 10);
+        assert_eq!(reward.last_update_time, 1_000);
+    }
+
+    #[test]
+    fn distributed_penalty_is_paid_to_stakers() {
+        let mut reward = RewardInfo::default();
+
+        distribute_reward(&mut reward, 9, 3);
+
+        assert_eq!(earned(1, reward.reward_per_token_stored, 0, 0), 3);
+        assert_eq!(earned(2, reward.reward_per_token_stored, 0, 0), 6);
+    }
+}
@@ -1,11 +1,229 @@
-use crate::constants::TIER_INFO;
-
-pub fn get_tier(amount: u64) -> u8 {
-  for (i, x) in TIER_INFO.iter().enumerate() {
-    if amount < *x {
-      return i as u8;
-    }
-  }
-
-  return TIER_INFO.len() as u8;
-}
+use crate::account::StakeLot;
+use crate::constants::{BPS_DENOMINATOR, MAX_STAKE_LOTS, TIER_COUNT};
+
+pub fn get_tier(tier_thresholds: &[u64; TIER_COUNT], amount: u64) -> u8 {
+  for (i, x) in tier_thresholds.iter().enumerate() {
+    if amount < *x {
+      return i as u8;
+    }
+  }
+
+  tier_thresholds.len() as u8
+}
+
+pub fn early_unstake_penalty(
+  amount: u64,
+  penalty_bps: u64,
+  penalty_decay: bool,
+  maturity_time: u64,
+  lock_period: u64,
+  now: u64,
+) -> u64 {
+  if maturity_time <= now {
+    return 0;
+  }
+
+  let mut penalty = (amount as u128) * (penalty_bps as u128) / (BPS_DENOMINATOR as u128);
+  if penalty_decay {
+    if lock_period == 0 {
+      return 0;
+    }
+    let remaining = std::cmp::min(maturity_time - now, lock_period);
+    penalty = penalty * (remaining as u128) / (lock_period as u128);
+  }
+
+  penalty as u64
+}
+
+/// Adds a new lot. Matured lots are merged first to free slots, and when all
+/// slots are still in use the new amount joins the latest lot, so only that
+/// lot is locked again.
+pub fn add_stake_lot(
+  stake_lots: &mut [StakeLot; MAX_STAKE_LOTS],
+  amount: u64,
+  maturity_time: u64,
+  now: u64,
+) {
+  let mut matured: Option<usize> = None;
+  for i in 0..MAX_STAKE_LOTS {
+    if stake_lots[i].amount == 0 || stake_lots[i].maturity_time > now {
+      continue;
+    }
+    match matured {
+      Some(idx) => {
+        stake_lots[idx].amount = stake_lots[idx].amount.checked_add(stake_lots[i].amount).unwrap();
+        stake_lots[idx].maturity_time =
+          std::cmp::max(stake_lots[idx].maturity_time, stake_lots[i].maturity_time);
+        stake_lots[i] = StakeLot::default();
+      }
+      None => matured = Some(i),
+    }
+  }
+
+  let idx = stake_lots
+    .iter()
+    .position(|x| x.amount > 0 && x.maturity_time == maturity_time)
+    .or_else(|| stake_lots.iter().position(|x| x.amount == 0))
+    .unwrap_or_else(|| {
+      (0..MAX_STAKE_LOTS)
+        .max_by_key(|i| stake_lots[*i].maturity_time)
+        .unwrap()
+    });
+
+  stake_lots[idx].amount = stake_lots[idx].amount.checked_add(amount).unwrap();
+  stake_lots[idx].maturity_time = std::cmp::max(stake_lots[idx].maturity_time, maturity_time);
+}
+
+/// Removes `amount` from the lots, earliest maturity first so matured lots are
+/// released before locked ones. `on_release` receives each removed amount with
+/// the maturity time of its lot.
+pub fn release_stake_lots<F: FnMut(u64, u64)>(
+  stake_lots: &mut [StakeLot; MAX_STAKE_LOTS],
+  amount: u64,
+  mut on_release: F,
+) {
+  let mut remaining = amount;
+  while remaining > 0 {
+    let idx = (0..MAX_STAKE_LOTS)
+      .filter(|i| stake_lots[*i].amount > 0)
+      .min_by_key(|i| stake_lots[*i].maturity_time)
+      .unwrap();
+
+    let released = std::cmp::min(remaining, stake_lots[idx].amount);
+    on_release(released, stake_lots[idx].maturity_time);
+
+    stake_lots[idx].amount -= released;
+    if stake_lots[idx].amount == 0 {
+      stake_lots[idx] = StakeLot::default();
+    }
+    remaining -= released;
+  }
+}
+
+pub fn unlocked_amount(stake_lots: &[StakeLot; MAX_STAKE_LOTS], now: u64) -> u64 {
+  stake_lots
+    .iter()
+    .filter(|x| x.maturity_time <= now)
+    .map(|x| x.amount)
+    .sum()
+}
+
+pub fn latest_maturity_time(stake_lots: &[StakeLot; MAX_STAKE_LOTS]) -> u64 {
+  stake_lots
+    .iter()
+    .filter(|x| x.amount > 0)
+    .map(|x| x.maturity_time)
+    .max()
+    .unwrap_or(0)
+}
+
+/// Maturity time of the earliest lot, zero when nothing is staked.
+pub fn earliest_maturity_time(stake_lots: &[StakeLot; MAX_STAKE_LOTS]) -> u64 {
+  stake_lots
+    .iter()
+    .filter(|x| x.amount > 0)
+    .map(|x| x.maturity_time)
+    .min()
+    .unwrap_or(0)
+}
+
+pub fn is_valid_tier_thresholds(tier_thresholds: &[u64; TIER_COUNT]) -> bool {
+  tier_thresholds[0] > 0 && tier_thresholds.windows(2).all(|x| x[0] < x[1])
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lot(amount: u64, maturity_time: u64) -> StakeLot {
+    StakeLot {
+      amount,
+      maturity_time,
+    }
+  }
+
+  #[test]
+  fn add_stake_lot_merges_matured_lots() {
+    let mut stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+    stake_lots[0] = lot(100, 10);
+    stake_lots[1] = lot(200, 20);
+    stake_lots[2] = lot(300, 50);
+
+    add_stake_lot(&mut stake_lots, 400, 60, 30);
+
+    assert_eq!(stake_lots[0].amount, 300);
+    assert_eq!(stake_lots[0].maturity_time, 20);
+    assert_eq!(stake_lots[1].amount, 400);
+    assert_eq!(stake_lots[1].maturity_time, 60);
+    assert_eq!(stake_lots[2].amount, 300);
+    assert_eq!(stake_lots[2].maturity_time, 50);
+  }
+
+  #[test]
+  fn add_stake_lot_joins_lot_with_same_maturity() {
+    let mut stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+    stake_lots[0] = lot(100, 60);
+
+    add_stake_lot(&mut stake_lots, 50, 60, 30);
+
+    assert_eq!(stake_lots[0].amount, 150);
+    assert_eq!(stake_lots[1].amount, 0);
+  }
+
+  #[test]
+  fn add_stake_lot_joins_latest_lot_when_full() {
+    let mut stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+    for (i, x) in stake_lots.iter_mut().enumerate() {
+      *x = lot(100, 40 + i as u64);
+    }
+
+    add_stake_lot(&mut stake_lots, 50, 60, 30);
+
+    assert_eq!(stake_lots[MAX_STAKE_LOTS - 1].amount, 150);
+    assert_eq!(stake_lots[MAX_STAKE_LOTS - 1].maturity_time, 60);
+    assert_eq!(stake_lots[0].amount, 100);
+    assert_eq!(stake_lots[0].maturity_time, 40);
+  }
+
+  #[test]
+  fn release_stake_lots_releases_earliest_first() {
+    let mut stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+    stake_lots[0] = lot(100, 50);
+    stake_lots[1] = lot(200, 10);
+    stake_lots[2] = lot(300, 30);
+
+    let mut released = vec![];
+    release_stake_lots(&mut stake_lots, 350, |amount, maturity_time| {
+      released.push((amount, maturity_time))
+    });
+
+    assert_eq!(released, vec![(200, 10), (150, 30)]);
+    assert_eq!(stake_lots[0].amount, 100);
+    assert_eq!(stake_lots[1].amount, 0);
+    assert_eq!(stake_lots[1].maturity_time, 0);
+    assert_eq!(stake_lots[2].amount, 150);
+  }
+
+  #[test]
+  fn unlocked_amount_counts_matured_lots() {
+    let mut stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+    stake_lots[0] = lot(100, 10);
+    stake_lots[1] = lot(200, 30);
+    stake_lots[2] = lot(300, 50);
+
+    assert_eq!(unlocked_amount(&stake_lots, 5), 0);
+    assert_eq!(unlocked_amount(&stake_lots, 30), 300);
+    assert_eq!(unlocked_amount(&stake_lots, 50), 600);
+  }
+
+  #[test]
+  fn earliest_maturity_time_ignores_empty_lots() {
+    let mut stake_lots = [StakeLot::default(); MAX_STAKE_LOTS];
+    assert_eq!(earliest_maturity_time(&stake_lots), 0);
+
+    stake_lots[3] = lot(100, 50);
+    stake_lots[5] = lot(100, 30);
+    assert_eq!(earliest_maturity_time(&stake_lots), 30);
+    assert_eq!(latest_maturity_time(&stake_lots), 50);
+  }
+}
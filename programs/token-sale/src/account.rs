@@ -1,79 +1,180 @@
-use anchor_lang::prelude::*;
-
-#[account]
-#[derive(Default)]
-pub struct TokenSaleConfig {
-    /// owner address
-    pub owner: Pubkey,
-    /// Nonce to derive the program-derived address owning the vaults.
-    pub nonce: u8,
-    /// 7 days lock staking pool
-    pub staking_pool_0: Pubkey,
-    /// 2 months lock staking pool
-    pub staking_pool_1: Pubkey,
-    /// Whitelist config address
-    pub whitelist_config: Pubkey,
-    /// Payment token mint (USDC)
-    pub payment_token_mint: Pubkey,
-    /// Payment token account
-    pub payment_token_vault: Pubkey,
-    /// Maximum payment token amount for sale
-    pub amount: u64,
-    /// Current paid amount
-    pub paid_amount: u64,
-    /// Withdrawn amount
-    pub withdrawn_amount: u64,
-    /// IDO start time
-    pub start_time: u64,
-    /// IDO period
-    pub period: u64,
-    /// staking 0 allocation per tier
-    pub staking_allocation_0: [u64; 7],
-    /// staking 1 allocation per tier
-    pub staking_allocation_1: [u64; 7],
-    /// NFT allocation
-    pub nft_allocation: u64,
-}
-
-#[account]
-#[derive(Default)]
-pub struct WhitelistAlloc {
-    /// Token sale config
-    pub config: Pubkey,
-    /// User address
-    pub user: Pubkey,
-    /// Allocation
-    pub allocation: u64,
-    /// Nonce
-    pub nonce: u8,
-}
-
-#[account]
-#[derive(Default)]
-pub struct UserTokenSale {
-    /// User address
-    pub user: Pubkey,
-    /// Token sale config
-    pub config: Pubkey,
-    /// Paid amount
-    pub paid_amount: u64,
-    /// Used allocation from staking 0
-    pub used_staking0_alloc: u64,
-    /// Used allocation from staking 1
-    pub used_staking1_alloc: u64,
-    /// Used allocation from whitelist
-    pub used_whitelist_alloc: u64,
-    /// nonce
-    pub nonce: u8,
-}
-
-#[account]
-#[derive(Default)]
-pub struct NftIdoInfo {
-    /// Token sale config
-    pub config: Pubkey,
-    /// Used allocation
-    pub used_allocation: u64,
-    /// nonce
-    pub nonce: u8,
-}
+use crate::constants::{
+    MAX_NFT_ALLOCATION_OVERRIDES, MAX_SALE_PHASES, MAX_STAKING_POOLS, MAX_WITHDRAWAL_RECORDS,
+    STAKING_ALLOCATION_LEN,
+};
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(Default)]
+pub struct TokenSaleConfig {
+    /// owner address
+    pub owner: Pubkey,
+    /// Proposed owner, who must accept to take over the config
+    pub pending_owner: Pubkey,
+    /// Nonce to derive the program-derived address owning the vaults.
+    pub nonce: u8,
+    /// Number of eligible staking pools
+    pub staking_pool_count: u8,
+    /// Eligible staking pools
+    pub staking_pools: [Pubkey; MAX_STAKING_POOLS],
+    /// Whitelist config address
+    pub whitelist_config: Pubkey,
+    /// Merkle root over whitelisted (user, allocation) pairs, unset if zero
+    pub whitelist_merkle_root: [u8; 32],
+    /// Payment token mint (USDC)
+    pub payment_token_mint: Pubkey,
+    /// Payment token account
+    pub payment_token_vault: Pubkey,
+    /// Payment token decimals
+    pub payment_token_decimals: u8,
+    /// Sale token decimals
+    pub sale_token_decimals: u8,
+    /// Price in payment token base units per whole sale token
+    pub price: u64,
+    /// Maximum payment token amount for sale
+    pub amount: u64,
+    /// Minimum payment token amount for the sale to succeed
+    pub soft_cap: u64,
+    /// Whether the sale was cancelled by the owner
+    pub cancelled: bool,
+    /// Whether payments are committed and allocated pro rata after the sale
+    pub weighted: bool,
+    /// Commitment weight per staking tier in weighted mode
+    pub tier_weights: [u64; STAKING_ALLOCATION_LEN],
+    /// Committed amount in weighted mode
+    pub committed_amount: u64,
+    /// Sum of committed amounts multiplied by tier weights
+    pub weighted_commitment: u128,
+    /// Committed amount of settled commitments in weighted mode
+    pub settled_committed_amount: u64,
+    /// Current paid amount
+    pub paid_amount: u64,
+    /// Sold sale token amount
+    pub sold_amount: u64,
+    /// Withdrawn amount
+    pub withdrawn_amount: u64,
+    /// Refunded amount
+    pub refunded_amount: u64,
+    /// IDO start time
+    pub start_time: u64,
+    /// IDO period
+    pub period: u64,
+    /// Delay after the sale end before the owner can withdraw
+    pub withdraw_cliff: u64,
+    /// Number of sale phases, the whole sale is a single phase if zero
+    pub phase_count: u8,
+    /// Ordered sale phases
+    pub phases: [SalePhase; MAX_SALE_PHASES],
+    /// Allocation per tier for each eligible staking pool
+    pub staking_allocations: [[u64; STAKING_ALLOCATION_LEN]; MAX_STAKING_POOLS],
+    /// NFT allocation
+    pub nft_allocation: u64,
+    /// Maximum payment amount a wallet can deposit with NFTs, unlimited if zero
+    pub nft_wallet_cap: u64,
+    /// Number of per-collection NFT allocation overrides
+    pub nft_allocation_override_count: u8,
+    /// Per-collection NFT allocation overrides
+    pub nft_allocation_overrides: [NftAllocationOverride; MAX_NFT_ALLOCATION_OVERRIDES],
+    /// Number of withdrawals made by the owner
+    pub withdrawal_count: u64,
+    /// Latest withdrawals, oldest entries are overwritten
+    pub withdrawals: [WithdrawalRecord; MAX_WITHDRAWAL_RECORDS],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct SalePhase {
+    /// Phase start time
+    pub start_time: u64,
+    /// Phase end time
+    pub end_time: u64,
+    /// Bitmask of deposit paths open during the phase
+    pub deposit_paths: u8,
+    /// Multiplier applied to allocations during the phase, in basis points
+    pub allocation_multiplier_bps: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct WhitelistEntry {
+    /// User address
+    pub user: Pubkey,
+    /// Allocation
+    pub allocation: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct NftAllocationOverride {
+    /// Whitelisted NFT mint, or collection mint for NFTs whitelisted by
+    /// collection
+    pub mint: Pubkey,
+    /// Allocation per NFT
+    pub allocation: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct WithdrawalRecord {
+    /// Recipient token account
+    pub recipient: Pubkey,
+    /// Withdrawn amount
+    pub amount: u64,
+    /// Withdrawal time
+    pub timestamp: u64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct WhitelistAlloc {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Allocation
+    pub allocation: u64,
+    /// Nonce
+    pub nonce: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct UserTokenSale {
+    /// User address
+    pub user: Pubkey,
+    /// Token sale config
+    pub config: Pubkey,
+    /// Paid amount
+    pub paid_amount: u64,
+    /// Purchased sale token amount
+    pub purchased_amount: u64,
+    /// Committed amount in weighted mode
+    pub committed_amount: u64,
+    /// Committed amounts multiplied by tier weights
+    pub weighted_commitment: u128,
+    /// Whether the commitment was settled
+    pub settled: bool,
+    /// Used allocation from each eligible staking pool
+    pub used_staking_alloc: [u64; MAX_STAKING_POOLS],
+    /// Tier registered before the sale for each eligible staking pool
+    pub registered_tiers: [u8; MAX_STAKING_POOLS],
+    /// Whether a tier was registered for each eligible staking pool
+    pub tier_registered: [bool; MAX_STAKING_POOLS],
+    /// Used allocation from whitelist
+    pub used_whitelist_alloc: u64,
+    /// Used allocation from NFTs
+    pub used_nft_alloc: u64,
+    /// nonce
+    pub nonce: u8,
+}
+
+#[account]
+#[derive(Default)]
+pub struct NftIdoInfo {
+    /// Token sale config
+    pub config: Pubkey,
+    /// NFT mint
+    pub mint: Pubkey,
+    /// Verified collection of the NFT, default if whitelisted by mint
+    pub collection: Pubkey,
+    /// Used allocation
+    pub used_allocation: u64,
+    /// nonce
+    pub nonce: u8,
+}
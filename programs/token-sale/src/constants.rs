@@ -0,0 +1,25 @@
+/// Maximum number of staking pools eligible for a sale.
+pub const MAX_STAKING_POOLS: usize = 4;
+
+/// Maximum sale token decimals, keeping purchase amount conversions in u128.
+pub const MAX_SALE_TOKEN_DECIMALS: u8 = 18;
+
+/// Allocation entries per staking pool, one per staking tier from tier 0 to
+/// tier 7.
+pub const STAKING_ALLOCATION_LEN: usize = 8;
+
+/// Maximum number of per-collection NFT allocation overrides.
+pub const MAX_NFT_ALLOCATION_OVERRIDES: usize = 4;
+
+/// Maximum number of sale phases.
+pub const MAX_SALE_PHASES: usize = 3;
+
+pub const BPS_DENOMINATOR: u64 = 10_000;
+
+/// Deposit paths which can be enabled for a sale phase.
+pub const DEPOSIT_PATH_STAKING: u8 = 1;
+pub const DEPOSIT_PATH_WHITELIST: u8 = 1 << 1;
+pub const DEPOSIT_PATH_NFT: u8 = 1 << 2;
+
+/// Number of withdrawals kept in the sale config history.
+pub const MAX_WITHDRAWAL_RECORDS: usize = 8;
@@ -1,21 +1,95 @@
-use anchor_lang::prelude::*;
-
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Amount must be greater than zero.")]
-    AmountMustBeGreaterThanZero,
-    #[msg("Period must be greater than zero.")]
-    PeriodMustBeGreaterThanZero,
-    #[msg("Token sale not started.")]
-    TokenSaleNotStarted,
-    #[msg("Token sale ended.")]
-    TokenSaleEnded,
-    #[msg("Start time must be greater than now.")]
-    StartTimeMustBeGreaterThanNow,
-    #[msg("No available tokens.")]
-    NoAvailableTokens,
-    #[msg("Insufficient allocation.")]
-    InsufficientAllocation,
-    #[msg("Amount must be lower than remaining IDO amount.")]
-    AmountMustBeLowerThanRemainingIDOAmount,
-}
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero.")]
+    AmountMustBeGreaterThanZero,
+    #[msg("Period must be greater than zero.")]
+    PeriodMustBeGreaterThanZero,
+    #[msg("Token sale not started.")]
+    TokenSaleNotStarted,
+    #[msg("Token sale ended.")]
+    TokenSaleEnded,
+    #[msg("Start time must be greater than now.")]
+    StartTimeMustBeGreaterThanNow,
+    #[msg("No available tokens.")]
+    NoAvailableTokens,
+    #[msg("Insufficient allocation.")]
+    InsufficientAllocation,
+    #[msg("Amount must be lower than remaining IDO amount.")]
+    AmountMustBeLowerThanRemainingIDOAmount,
+    #[msg("Price must be greater than zero.")]
+    PriceMustBeGreaterThanZero,
+    #[msg("Amount is too small to purchase any tokens.")]
+    PurchaseAmountTooSmall,
+    #[msg("Soft cap cannot be greater than sale amount.")]
+    SoftCapCannotBeGreaterThanAmount,
+    #[msg("Token sale cancelled.")]
+    TokenSaleCancelled,
+    #[msg("Cannot cancel token sale after withdrawal.")]
+    CannotCancelAfterWithdrawal,
+    #[msg("Soft cap not reached.")]
+    SoftCapNotReached,
+    #[msg("Refund is not available.")]
+    RefundNotAvailable,
+    #[msg("Nothing to refund.")]
+    NothingToRefund,
+    #[msg("Cannot withdraw before token sale ends.")]
+    CannotWithdrawBeforeSaleEnds,
+    #[msg("Invalid staking pools.")]
+    InvalidStakingPools,
+    #[msg("Staking pool is not eligible for the token sale.")]
+    StakingPoolNotEligible,
+    #[msg("Staking account is not owned by the signer.")]
+    StakingAccountOwnerMismatch,
+    #[msg("Tier registration is closed.")]
+    TierRegistrationClosed,
+    #[msg("Stake must be locked until the token sale ends.")]
+    StakeNotLockedUntilSaleEnd,
+    #[msg("Tier is not registered.")]
+    TierNotRegistered,
+    #[msg("Token sale requires commitments.")]
+    CommitRequired,
+    #[msg("Token sale is not weighted.")]
+    TokenSaleNotWeighted,
+    #[msg("Token sale already started.")]
+    TokenSaleAlreadyStarted,
+    #[msg("Token sale not ended.")]
+    TokenSaleNotEnded,
+    #[msg("Already settled.")]
+    AlreadySettled,
+    #[msg("Nothing to settle.")]
+    NothingToSettle,
+    #[msg("Invalid sale phases.")]
+    InvalidSalePhases,
+    #[msg("No active sale phase.")]
+    NoActiveSalePhase,
+    #[msg("Deposit path is not allowed in the current phase.")]
+    DepositPathNotAllowed,
+    #[msg("Invalid NFT allocation overrides.")]
+    InvalidNftAllocationOverrides,
+    #[msg("NFT deposit cap per wallet exceeded.")]
+    NftWalletCapExceeded,
+    #[msg("Invalid NFT metadata.")]
+    InvalidNftMetadata,
+    #[msg("NFT is not a verified member of the collection.")]
+    NftNotInVerifiedCollection,
+    #[msg("Invalid whitelist Merkle proof.")]
+    InvalidMerkleProof,
+    #[msg("Remaining accounts do not match the batch.")]
+    InvalidBatchAccounts,
+    #[msg("Invalid whitelist account.")]
+    InvalidWhitelistAccount,
+    #[msg("Allocation cannot be lower than the used allocation.")]
+    AllocationBelowUsedAmount,
+    #[msg("Signer is not the pending owner.")]
+    NotPendingOwner,
+    #[msg("Sale token decimals cannot be greater than 18.")]
+    InvalidSaleTokenDecimals,
+    #[msg("Purchase amount overflows.")]
+    PurchaseAmountOverflow,
+    #[msg("Cannot cancel token sale after it ends.")]
+    CannotCancelAfterSaleEnds,
+    #[msg("Tier weights cannot all be zero.")]
+    InvalidTierWeights,
+}
@@ -0,0 +1,242 @@
+use crate::constants::STAKING_ALLOCATION_LEN;
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct SaleInitialized {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Sale owner
+    pub owner: Pubkey,
+    /// Payment token mint
+    pub payment_token_mint: Pubkey,
+    /// Price of one sale token in payment tokens
+    pub price: u64,
+    /// Payment amount on sale
+    pub amount: u64,
+    /// Payment amount to raise for the sale to succeed
+    pub soft_cap: u64,
+    /// Sale start time
+    pub start_time: u64,
+    /// Sale period
+    pub period: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Whitelisted {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Whitelist allocation
+    pub allocation: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistRemoved {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct TierRegistered {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Staking pool the tier was registered from
+    pub staking_pool: Pubkey,
+    /// Amount locked until the sale ends
+    pub locked_amount: u64,
+    /// Registered tier
+    pub tier: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositedWithStaking {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Staking pool which granted the allocation
+    pub staking_pool: Pubkey,
+    /// User's staking tier
+    pub tier: u8,
+    /// Deposited amount
+    pub amount: u64,
+    /// User's paid amount after the deposit
+    pub paid_amount: u64,
+    /// Sale's paid amount after the deposit
+    pub total_paid_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositedWithWhitelist {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Deposited amount
+    pub amount: u64,
+    /// User's paid amount after the deposit
+    pub paid_amount: u64,
+    /// Sale's paid amount after the deposit
+    pub total_paid_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositedWithNft {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// NFT mint which granted the allocation
+    pub nft_mint: Pubkey,
+    /// Deposited amount
+    pub amount: u64,
+    /// User's paid amount after the deposit
+    pub paid_amount: u64,
+    /// Sale's paid amount after the deposit
+    pub total_paid_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Committed {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Staking pool the tier was taken from
+    pub staking_pool: Pubkey,
+    /// Tier used for the commitment weight
+    pub tier: u8,
+    /// Committed amount
+    pub amount: u64,
+    /// User's committed amount after the commitment
+    pub committed_amount: u64,
+    /// Sale's committed amount after the commitment
+    pub total_committed_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Settled {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Final paid amount
+    pub paid_amount: u64,
+    /// Purchased sale token amount
+    pub purchased_amount: u64,
+    /// Surplus payment returned to the user
+    pub refunded_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SaleCancelled {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Sale's paid amount at cancellation
+    pub paid_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Refunded {
+    /// Token sale config
+    pub config: Pubkey,
+    /// User address
+    pub user: Pubkey,
+    /// Refunded amount
+    pub amount: u64,
+    /// Sale's refunded amount after the refund
+    pub refunded_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Withdrawn {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Recipient token account
+    pub recipient: Pubkey,
+    /// Withdrawn amount
+    pub amount: u64,
+    /// Sale's withdrawn amount after the withdrawal
+    pub withdrawn_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipProposed {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Current owner
+    pub owner: Pubkey,
+    /// Proposed owner
+    pub pending_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Previous owner
+    pub previous_owner: Pubkey,
+    /// New owner
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct PhasesUpdated {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Number of sale phases, zero if the sale has no phases
+    pub phase_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistMerkleRootUpdated {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Merkle root of the whitelist, all zeros if disabled
+    pub root: [u8; 32],
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NftAllocationUpdated {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Default allocation per NFT
+    pub nft_allocation: u64,
+    /// Maximum payment amount a wallet can deposit with NFTs, unlimited if zero
+    pub nft_wallet_cap: u64,
+    /// Number of per-NFT allocation overrides
+    pub override_count: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WeightedModeUpdated {
+    /// Token sale config
+    pub config: Pubkey,
+    /// Whether staking deposits are committed and settled by tier weight
+    pub weighted: bool,
+    /// Weight of each staking tier
+    pub tier_weights: [u64; STAKING_ALLOCATION_LEN],
+    pub timestamp: i64,
+}
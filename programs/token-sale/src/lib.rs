@@ -1,299 +1,1444 @@
-pub mod account;
-pub mod context;
-pub mod error;
-
-use anchor_lang::prelude::*;
-use anchor_lang::solana_program::sysvar;
-use anchor_spl::token::{self};
-use context::*;
-use error::ErrorCode;
-use std::convert::Into;
-use std::convert::TryInto;
-
-declare_id!("CKtaZDifHb9EbhW9StG42FuR673mQykFFPDEST4h2rhB");
-
-#[program]
-pub mod token_sale {
-    use super::*;
-
-    pub fn initialize_sale(
-        ctx: Context<InitializeSale>,
-        nonce: u8,
-        amount: u64,
-        start_time: u64,
-        period: u64,
-        staking_allocation_0: [u64; 7],
-        staking_allocation_1: [u64; 7],
-        nft_allocation: u64,
-    ) -> Result<()> {
-        if amount == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-        if start_time
-            < sysvar::clock::Clock::get()
-                .unwrap()
-                .unix_timestamp
-                .try_into()
-                .unwrap()
-        {
-            return Err(ErrorCode::StartTimeMustBeGreaterThanNow.into());
-        }
-        if period == 0 {
-            return Err(ErrorCode::PeriodMustBeGreaterThanZero.into());
-        }
-
-        let config = &mut ctx.accounts.config;
-        config.owner = ctx.accounts.signer.key();
-        config.nonce = nonce;
-        config.staking_pool_0 = ctx.accounts.staking_pool_0.key();
-        config.staking_pool_1 = ctx.accounts.staking_pool_1.key();
-        config.whitelist_config = ctx.accounts.whitelist_config.key();
-        config.payment_token_mint = ctx.accounts.payment_token_mint.key();
-        config.payment_token_vault = ctx.accounts.payment_token_vault.key();
-        config.amount = amount;
-        config.paid_amount = 0;
-        config.withdrawn_amount = 0;
-        config.start_time = start_time;
-        config.period = period;
-        config.staking_allocation_0 = staking_allocation_0;
-        config.staking_allocation_1 = staking_allocation_1;
-        config.nft_allocation = nft_allocation;
-
-        Ok(())
-    }
-
-    pub fn whitelist(ctx: Context<Whitelist>, user: Pubkey, allocation: u64) -> Result<()> {
-        if allocation == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-
-        let whitelist_alloc = &mut ctx.accounts.whitelist_alloc;
-        whitelist_alloc.config = ctx.accounts.config.key();
-        whitelist_alloc.user = user;
-        whitelist_alloc.allocation = allocation;
-        whitelist_alloc.nonce = *ctx.bumps.get("whitelist_alloc").unwrap();
-
-        Ok(())
-    }
-
-    pub fn remove_whitelist(ctx: Context<RemoveWhitelist>, _user: Pubkey) -> Result<()> {
-        let whitelist_alloc = &mut ctx.accounts.whitelist_alloc;
-        whitelist_alloc.allocation = 0;
-
-        Ok(())
-    }
-
-    pub fn create_token_sale_account(ctx: Context<CreateTokenSaleAccount>) -> Result<()> {
-        let user_token_sale = &mut ctx.accounts.user_token_sale;
-        user_token_sale.user = ctx.accounts.signer.key();
-        user_token_sale.config = ctx.accounts.config.key();
-        user_token_sale.paid_amount = 0;
-        user_token_sale.used_staking0_alloc = 0;
-        user_token_sale.used_staking1_alloc = 0;
-        user_token_sale.used_whitelist_alloc = 0;
-        user_token_sale.nonce = *ctx.bumps.get("user_token_sale").unwrap();
-
-        Ok(())
-    }
-
-    pub fn create_nft_sale_account(ctx: Context<CreateNftSaleAccount>) -> Result<()> {
-        let nft_ido_info = &mut ctx.accounts.nft_ido_info;
-        nft_ido_info.config = ctx.accounts.config.key();
-        nft_ido_info.used_allocation = 0;
-        nft_ido_info.nonce = *ctx.bumps.get("nft_ido_info").unwrap();
-
-        Ok(())
-    }
-
-    pub fn deposit_with_staking(ctx: Context<DepositWithStaking>, amount: u64) -> Result<()> {
-        if amount == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-        let current_time = sysvar::clock::Clock::get()
-            .unwrap()
-            .unix_timestamp
-            .try_into()
-            .unwrap();
-
-        let config = &mut ctx.accounts.config;
-
-        if config.start_time > current_time {
-            return Err(ErrorCode::TokenSaleNotStarted.into());
-        }
-        if config.start_time + config.period < current_time {
-            return Err(ErrorCode::TokenSaleEnded.into());
-        }
-
-        if config.amount < config.paid_amount + amount {
-            return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
-        }
-
-        let user_token_sale = &mut ctx.accounts.user_token_sale;
-
-        let mut allocation: u64 = 0;
-        let mut used_allocation: u64 = 0;
-        if ctx.accounts.staking_account.pool == config.staking_pool_0 {
-            let tier = ctx.accounts.staking_account.tier;
-            allocation = config.staking_allocation_0[(tier - 1) as usize];
-            used_allocation = user_token_sale.used_staking0_alloc;
-            user_token_sale.used_staking0_alloc += amount;
-        } else if ctx.accounts.staking_account.pool == config.staking_pool_1 {
-            let tier = ctx.accounts.staking_account.tier;
-            allocation = config.staking_allocation_1[(tier - 1) as usize];
-            used_allocation = user_token_sale.used_staking1_alloc;
-            user_token_sale.used_staking1_alloc += amount;
-        }
-
-        if allocation < used_allocation + amount {
-            return Err(ErrorCode::InsufficientAllocation.into());
-        }
-
-        user_token_sale.paid_amount += amount;
-        config.paid_amount += amount;
-
-        // Transfer tokens into the IDO vault.
-        {
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_vault.to_account_info(),
-                    to: ctx.accounts.payment_token_vault.to_account_info(),
-                    authority: ctx.accounts.signer.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, amount)?;
-        }
-
-        Ok(())
-    }
-
-    pub fn deposit_with_whitelist(ctx: Context<DepositWithWhitelist>, amount: u64) -> Result<()> {
-        if amount == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-        let current_time = sysvar::clock::Clock::get()
-            .unwrap()
-            .unix_timestamp
-            .try_into()
-            .unwrap();
-
-        let config = &mut ctx.accounts.config;
-
-        if config.start_time > current_time {
-            return Err(ErrorCode::TokenSaleNotStarted.into());
-        }
-        if config.start_time + config.period < current_time {
-            return Err(ErrorCode::TokenSaleEnded.into());
-        }
-
-        if config.amount < config.paid_amount + amount {
-            return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
-        }
-
-        let user_token_sale = &mut ctx.accounts.user_token_sale;
-
-        if user_token_sale.used_whitelist_alloc + amount > ctx.accounts.whitelist_alloc.allocation {
-            return Err(ErrorCode::InsufficientAllocation.into());
-        }
-
-        user_token_sale.used_whitelist_alloc += amount;
-
-        user_token_sale.paid_amount += amount;
-        config.paid_amount += amount;
-
-        // Transfer tokens into the IDO vault.
-        {
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_vault.to_account_info(),
-                    to: ctx.accounts.payment_token_vault.to_account_info(),
-                    authority: ctx.accounts.signer.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, amount)?;
-        }
-
-        Ok(())
-    }
-
-    pub fn deposit_with_nft(ctx: Context<DepositWithNft>, amount: u64) -> Result<()> {
-        if amount == 0 {
-            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
-        }
-        let current_time = sysvar::clock::Clock::get()
-            .unwrap()
-            .unix_timestamp
-            .try_into()
-            .unwrap();
-
-        let config = &mut ctx.accounts.config;
-
-        if config.amount < config.paid_amount + amount {
-            return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
-        }
-
-        if config.start_time > current_time {
-            return Err(ErrorCode::TokenSaleNotStarted.into());
-        }
-        if config.start_time + config.period < current_time {
-            return Err(ErrorCode::TokenSaleEnded.into());
-        }
-
-        let nft_ido_info = &mut ctx.accounts.nft_ido_info;
-        if config.nft_allocation - nft_ido_info.used_allocation < amount {
-            return Err(ErrorCode::InsufficientAllocation.into());
-        }
-        if config.amount < config.paid_amount + amount {
-            return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
-        }
-        let user_token_sale = &mut ctx.accounts.user_token_sale;
-
-        user_token_sale.paid_amount += amount;
-        config.paid_amount += amount;
-        nft_ido_info.used_allocation += amount;
-
-        // Transfer tokens into the IDO vault.
-        {
-            let cpi_ctx = CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.user_vault.to_account_info(),
-                    to: ctx.accounts.payment_token_vault.to_account_info(),
-                    authority: ctx.accounts.signer.to_account_info(),
-                },
-            );
-            token::transfer(cpi_ctx, amount)?;
-        }
-
-        Ok(())
-    }
-
-    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
-        let config = &mut ctx.accounts.config;
-
-        config.withdrawn_amount += ctx.accounts.payment_token_vault.amount;
-
-        // Transfer tokens from the vault to recipient
-        {
-            let seeds = &[
-                ctx.accounts.config.to_account_info().key.as_ref(),
-                &[ctx.accounts.config.nonce],
-            ];
-            let sale_signer = &[&seeds[..]];
-
-            let cpi_ctx = CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                token::Transfer {
-                    from: ctx.accounts.payment_token_vault.to_account_info(),
-                    to: ctx.accounts.recipient.to_account_info(),
-                    authority: ctx.accounts.sale_signer.to_account_info(),
-                },
-                sale_signer,
-            );
-            token::transfer(cpi_ctx, ctx.accounts.payment_token_vault.amount)?;
-        }
-
-        Ok(())
-    }
-}
+pub mod account;
+pub mod constants;
+pub mod context;
+pub mod error;
+pub mod event;
+pub mod merkle;
+pub mod metadata;
+pub mod utils;
+
+use account::{
+    NftAllocationOverride, NftIdoInfo, SalePhase, TokenSaleConfig, UserTokenSale, WhitelistAlloc,
+    WhitelistEntry, WithdrawalRecord,
+};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar;
+use anchor_spl::token::{self};
+use constants::*;
+use context::*;
+use error::ErrorCode;
+use event::*;
+use metadata::verify_collection;
+use program_utils::init_or_load;
+use staking::account::{Pool as StakingPool, User as UserStaking};
+use staking::utils::get_tier;
+use std::convert::Into;
+use std::convert::TryInto;
+use utils::*;
+
+declare_id!("CKtaZDifHb9EbhW9StG42FuR673mQykFFPDEST4h2rhB");
+
+pub fn staking_pool_index(config: &TokenSaleConfig, staking_pool: Pubkey) -> Result<usize> {
+    match config.staking_pools[..config.staking_pool_count as usize]
+        .iter()
+        .position(|x| *x == staking_pool)
+    {
+        Some(index) => Ok(index),
+        None => Err(ErrorCode::StakingPoolNotEligible.into()),
+    }
+}
+
+/// Returns the eligible pool index and the tier to use for a staking account.
+pub fn staking_tier(
+    config: &TokenSaleConfig,
+    user_token_sale: &UserTokenSale,
+    staking_account: &UserStaking,
+) -> Result<(usize, u8)> {
+    let pool_index = staking_pool_index(config, staking_account.pool)?;
+    if !user_token_sale.tier_registered[pool_index] {
+        return Err(ErrorCode::TierNotRegistered.into());
+    }
+
+    // Use the tier registered before the sale, unless the stake has since
+    // been reduced below it.
+    let tier = std::cmp::min(
+        user_token_sale.registered_tiers[pool_index],
+        staking_account.tier,
+    );
+
+    Ok((pool_index, tier))
+}
+
+/// Returns the allocation multiplier of the phase open at `current_time` for
+/// a deposit path. Sales without phases accept every path at 1x.
+pub fn phase_multiplier(
+    config: &TokenSaleConfig,
+    deposit_path: u8,
+    current_time: u64,
+) -> Result<u64> {
+    if config.phase_count == 0 {
+        return Ok(BPS_DENOMINATOR);
+    }
+
+    let phase = match config.phases[..config.phase_count as usize]
+        .iter()
+        .find(|x| x.start_time <= current_time && current_time <= x.end_time)
+    {
+        Some(phase) => phase,
+        None => return Err(ErrorCode::NoActiveSalePhase.into()),
+    };
+    if phase.deposit_paths & deposit_path == 0 {
+        return Err(ErrorCode::DepositPathNotAllowed.into());
+    }
+
+    Ok(phase.allocation_multiplier_bps)
+}
+
+/// Allocation per NFT, overridden for some whitelist entries: the NFT mint,
+/// or the collection for NFTs whitelisted by collection.
+pub fn nft_allocation(config: &TokenSaleConfig, nft_ido_info: &NftIdoInfo) -> u64 {
+    let whitelisted_mint = if nft_ido_info.collection == Pubkey::default() {
+        nft_ido_info.mint
+    } else {
+        nft_ido_info.collection
+    };
+
+    match config.nft_allocation_overrides[..config.nft_allocation_override_count as usize]
+        .iter()
+        .find(|x| x.mint == whitelisted_mint)
+    {
+        Some(x) => x.allocation,
+        None => config.nft_allocation,
+    }
+}
+
+/// Checks a whitelist deposit against the sale and `allocation` and records it.
+pub fn record_whitelist_deposit(
+    config: &mut TokenSaleConfig,
+    user_token_sale: &mut UserTokenSale,
+    allocation: u64,
+    amount: u64,
+    current_time: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+    }
+
+    if config.start_time > current_time {
+        return Err(ErrorCode::TokenSaleNotStarted.into());
+    }
+    if is_sale_ended(config, current_time) {
+        return Err(ErrorCode::TokenSaleEnded.into());
+    }
+
+    if config.amount < config.paid_amount + amount {
+        return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
+    }
+
+    let multiplier = phase_multiplier(config, DEPOSIT_PATH_WHITELIST, current_time)?;
+    let allocation = apply_multiplier(allocation, multiplier);
+    if user_token_sale.used_whitelist_alloc + amount > allocation {
+        return Err(ErrorCode::InsufficientAllocation.into());
+    }
+
+    user_token_sale.used_whitelist_alloc += amount;
+
+    let purchased_amount = get_purchase_amount(amount, config.price, config.sale_token_decimals)
+        .ok_or(ErrorCode::PurchaseAmountOverflow)?;
+    if purchased_amount == 0 {
+        return Err(ErrorCode::PurchaseAmountTooSmall.into());
+    }
+
+    user_token_sale.paid_amount += amount;
+    user_token_sale.purchased_amount += purchased_amount;
+    config.paid_amount += amount;
+    config.sold_amount += purchased_amount;
+
+    Ok(())
+}
+
+/// Checks an NFT deposit against the sale and NFT allocations and records it.
+pub fn record_nft_deposit(
+    config: &mut TokenSaleConfig,
+    user_token_sale: &mut UserTokenSale,
+    nft_ido_info: &mut NftIdoInfo,
+    amount: u64,
+    current_time: u64,
+) -> Result<()> {
+    if amount == 0 {
+        return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+    }
+
+    if config.amount < config.paid_amount + amount {
+        return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
+    }
+
+    if config.start_time > current_time {
+        return Err(ErrorCode::TokenSaleNotStarted.into());
+    }
+    if is_sale_ended(config, current_time) {
+        return Err(ErrorCode::TokenSaleEnded.into());
+    }
+
+    let multiplier = phase_multiplier(config, DEPOSIT_PATH_NFT, current_time)?;
+    let allocation = apply_multiplier(nft_allocation(config, nft_ido_info), multiplier);
+
+    // The allocation can be lowered after NFTs were used.
+    if allocation.saturating_sub(nft_ido_info.used_allocation) < amount {
+        return Err(ErrorCode::InsufficientAllocation.into());
+    }
+    if config.nft_wallet_cap > 0 && user_token_sale.used_nft_alloc + amount > config.nft_wallet_cap
+    {
+        return Err(ErrorCode::NftWalletCapExceeded.into());
+    }
+
+    let purchased_amount = get_purchase_amount(amount, config.price, config.sale_token_decimals)
+        .ok_or(ErrorCode::PurchaseAmountOverflow)?;
+    if purchased_amount == 0 {
+        return Err(ErrorCode::PurchaseAmountTooSmall.into());
+    }
+
+    user_token_sale.paid_amount += amount;
+    user_token_sale.purchased_amount += purchased_amount;
+    config.paid_amount += amount;
+    config.sold_amount += purchased_amount;
+    nft_ido_info.used_allocation += amount;
+    user_token_sale.used_nft_alloc += amount;
+
+    Ok(())
+}
+
+/// Last time deposits and commitments are accepted.
+pub fn sale_end_time(config: &TokenSaleConfig) -> u64 {
+    config.start_time + config.period
+}
+
+/// Whether the sale has ended. Deposits are accepted up to and including the
+/// end time, so everything gated on the sale end starts strictly after it.
+pub fn is_sale_ended(config: &TokenSaleConfig, current_time: u64) -> bool {
+    current_time > sale_end_time(config)
+}
+
+/// Payment amount the sale has raised, used against the soft cap.
+pub fn raised_amount(config: &TokenSaleConfig) -> u64 {
+    if config.weighted {
+        std::cmp::min(config.committed_amount, config.amount)
+    } else {
+        config.paid_amount
+    }
+}
+
+/// Sale tokens owed to buyers once every payment is settled. In weighted mode
+/// this bounds what commitments settle to before they are all settled, and is
+/// the exact sold amount after.
+pub fn allocated_sale_amount(config: &TokenSaleConfig) -> Option<u64> {
+    if config.weighted && config.settled_committed_amount < config.committed_amount {
+        get_purchase_amount(
+            raised_amount(config),
+            config.price,
+            config.sale_token_decimals,
+        )
+    } else {
+        Some(config.sold_amount)
+    }
+}
+
+/// Final payment amount of a commitment in weighted mode. Commitments are
+/// filled in full when the sale is not oversubscribed, otherwise the sale
+/// amount is split pro rata by weighted commitment, or by commitment if only
+/// zero weight tiers committed.
+pub fn settled_amount(config: &TokenSaleConfig, user_token_sale: &UserTokenSale) -> u64 {
+    if config.committed_amount <= config.amount {
+        return user_token_sale.committed_amount;
+    }
+
+    let amount = ((config.amount as u128) * user_token_sale.weighted_commitment)
+        .checked_div(config.weighted_commitment)
+        .unwrap_or_else(|| {
+            (config.amount as u128) * (user_token_sale.committed_amount as u128)
+                / (config.committed_amount as u128)
+        });
+    std::cmp::min(amount as u64, user_token_sale.committed_amount)
+}
+
+/// Settles a commitment in weighted mode and records the purchase. Returns
+/// the surplus payment owed back to the user.
+pub fn record_settlement(
+    config: &mut TokenSaleConfig,
+    user_token_sale: &mut UserTokenSale,
+) -> Result<u64> {
+    if user_token_sale.settled {
+        return Err(ErrorCode::AlreadySettled.into());
+    }
+    if user_token_sale.committed_amount == 0 {
+        return Err(ErrorCode::NothingToSettle.into());
+    }
+
+    let paid_amount = settled_amount(config, user_token_sale);
+    let purchased_amount =
+        get_purchase_amount(paid_amount, config.price, config.sale_token_decimals)
+            .ok_or(ErrorCode::PurchaseAmountOverflow)?;
+
+    user_token_sale.paid_amount = paid_amount;
+    user_token_sale.purchased_amount = purchased_amount;
+    user_token_sale.settled = true;
+    config.paid_amount += paid_amount;
+    config.sold_amount += purchased_amount;
+    config.settled_committed_amount += user_token_sale.committed_amount;
+
+    Ok(user_token_sale.committed_amount - paid_amount)
+}
+
+#[program]
+pub mod token_sale {
+    use super::*;
+
+    // Eligible staking pools are passed as remaining accounts, in the same
+    // order as `staking_allocations`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_sale<'info>(
+        ctx: Context<'_, '_, '_, 'info, InitializeSale<'info>>,
+        nonce: u8,
+        price: u64,
+        sale_token_decimals: u8,
+        amount: u64,
+        soft_cap: u64,
+        start_time: u64,
+        period: u64,
+        withdraw_cliff: u64,
+        staking_allocations: Vec<[u64; STAKING_ALLOCATION_LEN]>,
+        nft_allocation: u64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+        if start_time
+            < sysvar::clock::Clock::get()
+                .unwrap()
+                .unix_timestamp
+                .try_into()
+                .unwrap()
+        {
+            return Err(ErrorCode::StartTimeMustBeGreaterThanNow.into());
+        }
+        if period == 0 {
+            return Err(ErrorCode::PeriodMustBeGreaterThanZero.into());
+        }
+        if price == 0 {
+            return Err(ErrorCode::PriceMustBeGreaterThanZero.into());
+        }
+        if sale_token_decimals > MAX_SALE_TOKEN_DECIMALS {
+            return Err(ErrorCode::InvalidSaleTokenDecimals.into());
+        }
+        if soft_cap > amount {
+            return Err(ErrorCode::SoftCapCannotBeGreaterThanAmount.into());
+        }
+        if staking_allocations.len() > MAX_STAKING_POOLS
+            || staking_allocations.len() != ctx.remaining_accounts.len()
+        {
+            return Err(ErrorCode::InvalidStakingPools.into());
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.owner = ctx.accounts.signer.key();
+        config.pending_owner = Pubkey::default();
+        config.nonce = nonce;
+        for (i, account) in ctx.remaining_accounts.iter().enumerate() {
+            let staking_pool: Account<StakingPool> = Account::try_from(account)?;
+            if config.staking_pools[..i].contains(&staking_pool.key()) {
+                return Err(ErrorCode::InvalidStakingPools.into());
+            }
+            config.staking_pools[i] = staking_pool.key();
+            config.staking_allocations[i] = staking_allocations[i];
+        }
+        config.staking_pool_count = staking_allocations.len() as u8;
+        config.whitelist_config = ctx.accounts.whitelist_config.key();
+        config.whitelist_merkle_root = [0; 32];
+        config.payment_token_mint = ctx.accounts.payment_token_mint.key();
+        config.payment_token_vault = ctx.accounts.payment_token_vault.key();
+        config.payment_token_decimals = ctx.accounts.payment_token_mint.decimals;
+        config.sale_token_decimals = sale_token_decimals;
+        config.price = price;
+        config.amount = amount;
+        config.soft_cap = soft_cap;
+        config.cancelled = false;
+        config.weighted = false;
+        config.committed_amount = 0;
+        config.weighted_commitment = 0;
+        config.settled_committed_amount = 0;
+        config.paid_amount = 0;
+        config.sold_amount = 0;
+        config.withdrawn_amount = 0;
+        config.refunded_amount = 0;
+        config.start_time = start_time;
+        config.period = period;
+        config.withdraw_cliff = withdraw_cliff;
+        config.nft_allocation = nft_allocation;
+        config.withdrawal_count = 0;
+
+        emit!(SaleInitialized {
+            config: config.key(),
+            owner: config.owner,
+            payment_token_mint: config.payment_token_mint,
+            price,
+            amount,
+            soft_cap,
+            start_time,
+            period,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn whitelist(ctx: Context<Whitelist>, user: Pubkey, allocation: u64) -> Result<()> {
+        if allocation == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+
+        let whitelist_alloc = &mut ctx.accounts.whitelist_alloc;
+        whitelist_alloc.config = ctx.accounts.config.key();
+        whitelist_alloc.user = user;
+        whitelist_alloc.allocation = allocation;
+        whitelist_alloc.nonce = *ctx.bumps.get("whitelist_alloc").unwrap();
+
+        emit!(Whitelisted {
+            config: whitelist_alloc.config,
+            user,
+            allocation,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Remaining accounts are the `WhitelistAlloc` and `UserTokenSale` PDAs of
+    /// each entry, in pairs.
+    pub fn batch_whitelist<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchWhitelist<'info>>,
+        entries: Vec<WhitelistEntry>,
+    ) -> Result<()> {
+        if entries.is_empty() || entries.len() * 2 != ctx.remaining_accounts.len() {
+            return Err(ErrorCode::InvalidBatchAccounts.into());
+        }
+
+        let config = ctx.accounts.config.key();
+        let timestamp = sysvar::clock::Clock::get().unwrap().unix_timestamp;
+        for (entry, accounts) in entries.iter().zip(ctx.remaining_accounts.chunks(2)) {
+            let (info, user_token_sale) = (&accounts[0], &accounts[1]);
+            if entry.allocation == 0 {
+                return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+            }
+
+            // The user may not have created a sale account yet.
+            let (user_token_sale_address, _) = Pubkey::find_program_address(
+                &[config.as_ref(), entry.user.as_ref()],
+                ctx.program_id,
+            );
+            if *user_token_sale.key != user_token_sale_address {
+                return Err(ErrorCode::InvalidWhitelistAccount.into());
+            }
+            if !user_token_sale.data_is_empty() {
+                let user_token_sale: Account<UserTokenSale> = Account::try_from(user_token_sale)?;
+                if entry.allocation < user_token_sale.used_whitelist_alloc {
+                    return Err(ErrorCode::AllocationBelowUsedAmount.into());
+                }
+            }
+
+            let (address, bump) = Pubkey::find_program_address(
+                &["whitelist".as_bytes(), config.as_ref(), entry.user.as_ref()],
+                ctx.program_id,
+            );
+            if *info.key != address {
+                return Err(ErrorCode::InvalidWhitelistAccount.into());
+            }
+
+            let mut whitelist_alloc: Account<WhitelistAlloc> = init_or_load(
+                info,
+                &[
+                    "whitelist".as_bytes(),
+                    config.as_ref(),
+                    entry.user.as_ref(),
+                    &[bump],
+                ],
+                &ctx.accounts.signer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            whitelist_alloc.config = config;
+            whitelist_alloc.user = entry.user;
+            whitelist_alloc.allocation = entry.allocation;
+            whitelist_alloc.nonce = bump;
+            whitelist_alloc.exit(ctx.program_id)?;
+
+            emit!(Whitelisted {
+                config,
+                user: entry.user,
+                allocation: entry.allocation,
+                timestamp,
+            });
+        }
+
+        Ok(())
+    }
+
+    pub fn update_whitelist_allocation(
+        ctx: Context<UpdateWhitelistAllocation>,
+        _user: Pubkey,
+        allocation: u64,
+    ) -> Result<()> {
+        if allocation == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+
+        // The user may not have created a sale account yet.
+        let user_token_sale = &ctx.accounts.user_token_sale;
+        if !user_token_sale.data_is_empty() {
+            let user_token_sale: Account<UserTokenSale> = Account::try_from(user_token_sale)?;
+            if allocation < user_token_sale.used_whitelist_alloc {
+                return Err(ErrorCode::AllocationBelowUsedAmount.into());
+            }
+        }
+
+        let whitelist_alloc = &mut ctx.accounts.whitelist_alloc;
+        whitelist_alloc.allocation = allocation;
+
+        emit!(Whitelisted {
+            config: whitelist_alloc.config,
+            user: whitelist_alloc.user,
+            allocation,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_whitelist(ctx: Context<RemoveWhitelist>, _user: Pubkey) -> Result<()> {
+        let whitelist_alloc = &mut ctx.accounts.whitelist_alloc;
+        whitelist_alloc.allocation = 0;
+
+        emit!(WhitelistRemoved {
+            config: whitelist_alloc.config,
+            user: whitelist_alloc.user,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_token_sale_account(ctx: Context<CreateTokenSaleAccount>) -> Result<()> {
+        let user_token_sale = &mut ctx.accounts.user_token_sale;
+        user_token_sale.user = ctx.accounts.signer.key();
+        user_token_sale.config = ctx.accounts.config.key();
+        user_token_sale.paid_amount = 0;
+        user_token_sale.purchased_amount = 0;
+        user_token_sale.committed_amount = 0;
+        user_token_sale.weighted_commitment = 0;
+        user_token_sale.settled = false;
+        user_token_sale.used_staking_alloc = [0; MAX_STAKING_POOLS];
+        user_token_sale.registered_tiers = [0; MAX_STAKING_POOLS];
+        user_token_sale.tier_registered = [false; MAX_STAKING_POOLS];
+        user_token_sale.used_whitelist_alloc = 0;
+        user_token_sale.used_nft_alloc = 0;
+        user_token_sale.nonce = *ctx.bumps.get("user_token_sale").unwrap();
+
+        Ok(())
+    }
+
+    pub fn create_nft_sale_account(ctx: Context<CreateNftSaleAccount>) -> Result<()> {
+        let nft_ido_info = &mut ctx.accounts.nft_ido_info;
+        nft_ido_info.config = ctx.accounts.config.key();
+        nft_ido_info.mint = ctx.accounts.nft_mint.key();
+        nft_ido_info.collection = Pubkey::default();
+        nft_ido_info.used_allocation = 0;
+        nft_ido_info.nonce = *ctx.bumps.get("nft_ido_info").unwrap();
+
+        Ok(())
+    }
+
+    // Records the tier given by the stake locked until the sale ends, so that
+    // deposits do not depend on stake added right before the sale.
+    pub fn register_tier(ctx: Context<RegisterTier>) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
+
+        let config = &ctx.accounts.config;
+        if config.start_time <= current_time {
+            return Err(ErrorCode::TierRegistrationClosed.into());
+        }
+
+        let pool_index = staking_pool_index(config, ctx.accounts.staking_pool.key())?;
+
+        let sale_end = sale_end_time(config);
+        let staking_account = &ctx.accounts.staking_account;
+        if staking_account.maturity_time < sale_end {
+            return Err(ErrorCode::StakeNotLockedUntilSaleEnd.into());
+        }
+
+        let locked_amount: u64 = staking_account
+            .stake_lots
+            .iter()
+            .filter(|x| x.maturity_time >= sale_end)
+            .map(|x| x.amount)
+            .sum();
+        let tier = get_tier(&ctx.accounts.staking_pool.tier_thresholds, locked_amount);
+
+        let user_token_sale = &mut ctx.accounts.user_token_sale;
+        user_token_sale.registered_tiers[pool_index] = tier;
+        user_token_sale.tier_registered[pool_index] = true;
+
+        emit!(TierRegistered {
+            config: config.key(),
+            user: ctx.accounts.signer.key(),
+            staking_pool: ctx.accounts.staking_pool.key(),
+            locked_amount,
+            tier,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_with_staking(ctx: Context<DepositWithStaking>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+
+        let config = &mut ctx.accounts.config;
+
+        if config.start_time > current_time {
+            return Err(ErrorCode::TokenSaleNotStarted.into());
+        }
+        if is_sale_ended(config, current_time) {
+            return Err(ErrorCode::TokenSaleEnded.into());
+        }
+
+        if config.amount < config.paid_amount + amount {
+            return Err(ErrorCode::AmountMustBeLowerThanRemainingIDOAmount.into());
+        }
+
+        let user_token_sale = &mut ctx.accounts.user_token_sale;
+
+        let multiplier = phase_multiplier(config, DEPOSIT_PATH_STAKING, current_time)?;
+        let (pool_index, tier) =
+            staking_tier(config, user_token_sale, &ctx.accounts.staking_account)?;
+        let allocation = apply_multiplier(
+            config.staking_allocations[pool_index][tier as usize],
+            multiplier,
+        );
+        let used_allocation = user_token_sale.used_staking_alloc[pool_index];
+
+        if allocation < used_allocation + amount {
+            return Err(ErrorCode::InsufficientAllocation.into());
+        }
+
+        user_token_sale.used_staking_alloc[pool_index] += amount;
+
+        let purchased_amount =
+            get_purchase_amount(amount, config.price, config.sale_token_decimals)
+                .ok_or(ErrorCode::PurchaseAmountOverflow)?;
+        if purchased_amount == 0 {
+            return Err(ErrorCode::PurchaseAmountTooSmall.into());
+        }
+
+        user_token_sale.paid_amount += amount;
+        user_token_sale.purchased_amount += purchased_amount;
+        config.paid_amount += amount;
+        config.sold_amount += purchased_amount;
+
+        // Transfer tokens into the IDO vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_vault.to_account_info(),
+                    to: ctx.accounts.payment_token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(DepositedWithStaking {
+            config: config.key(),
+            user: ctx.accounts.signer.key(),
+            staking_pool: ctx.accounts.staking_account.pool,
+            tier,
+            amount,
+            paid_amount: user_token_sale.paid_amount,
+            total_paid_amount: config.paid_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_with_whitelist(ctx: Context<DepositWithWhitelist>, amount: u64) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+
+        record_whitelist_deposit(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_token_sale,
+            ctx.accounts.whitelist_alloc.allocation,
+            amount,
+            current_time,
+        )?;
+
+        // Transfer tokens into the IDO vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_vault.to_account_info(),
+                    to: ctx.accounts.payment_token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(DepositedWithWhitelist {
+            config: ctx.accounts.config.key(),
+            user: ctx.accounts.signer.key(),
+            amount,
+            paid_amount: ctx.accounts.user_token_sale.paid_amount,
+            total_paid_amount: ctx.accounts.config.paid_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_whitelist_merkle_root(
+        ctx: Context<SetWhitelistMerkleRoot>,
+        root: [u8; 32],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        let current_time: u64 = sysvar::clock::Clock::get()
+            .unwrap()
+            .unix_timestamp
+            .try_into()
+            .unwrap();
+        if config.start_time <= current_time {
+            return Err(ErrorCode::TokenSaleAlreadyStarted.into());
+        }
+        config.whitelist_merkle_root = root;
+
+        emit!(WhitelistMerkleRootUpdated {
+            config: config.key(),
+            root,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_with_whitelist_proof(
+        ctx: Context<DepositWithWhitelistProof>,
+        amount: u64,
+        allocation: u64,
+        proof: Vec<[u8; 32]>,
+    ) -> Result<()> {
+        let root = ctx.accounts.config.whitelist_merkle_root;
+        if root == [0; 32]
+            || !merkle::verify(
+                &proof,
+                &root,
+                merkle::leaf(&ctx.accounts.signer.key(), allocation),
+            )
+        {
+            return Err(ErrorCode::InvalidMerkleProof.into());
+        }
+
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+
+        record_whitelist_deposit(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_token_sale,
+            allocation,
+            amount,
+            current_time,
+        )?;
+
+        // Transfer tokens into the IDO vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_vault.to_account_info(),
+                    to: ctx.accounts.payment_token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(DepositedWithWhitelist {
+            config: ctx.accounts.config.key(),
+            user: ctx.accounts.signer.key(),
+            amount,
+            paid_amount: ctx.accounts.user_token_sale.paid_amount,
+            total_paid_amount: ctx.accounts.config.paid_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn deposit_with_nft(ctx: Context<DepositWithNft>, amount: u64) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+
+        record_nft_deposit(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_token_sale,
+            &mut ctx.accounts.nft_ido_info,
+            amount,
+            current_time,
+        )?;
+
+        // Transfer tokens into the IDO vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_vault.to_account_info(),
+                    to: ctx.accounts.payment_token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(DepositedWithNft {
+            config: ctx.accounts.config.key(),
+            user: ctx.accounts.signer.key(),
+            nft_mint: ctx.accounts.nft_account.mint,
+            amount,
+            paid_amount: ctx.accounts.user_token_sale.paid_amount,
+            total_paid_amount: ctx.accounts.config.paid_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn create_collection_nft_sale_account(
+        ctx: Context<CreateCollectionNftSaleAccount>,
+    ) -> Result<()> {
+        let collection = ctx.accounts.whitelist_collection.collection;
+        verify_collection(
+            &ctx.accounts.nft_metadata,
+            &ctx.accounts.nft_mint.key(),
+            &collection,
+        )?;
+
+        let nft_ido_info = &mut ctx.accounts.nft_ido_info;
+        nft_ido_info.config = ctx.accounts.config.key();
+        nft_ido_info.mint = ctx.accounts.nft_mint.key();
+        nft_ido_info.collection = collection;
+        nft_ido_info.used_allocation = 0;
+        nft_ido_info.nonce = *ctx.bumps.get("nft_ido_info").unwrap();
+
+        Ok(())
+    }
+
+    pub fn deposit_with_collection_nft(
+        ctx: Context<DepositWithCollectionNft>,
+        amount: u64,
+    ) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+
+        record_nft_deposit(
+            &mut ctx.accounts.config,
+            &mut ctx.accounts.user_token_sale,
+            &mut ctx.accounts.nft_ido_info,
+            amount,
+            current_time,
+        )?;
+
+        // Transfer tokens into the IDO vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_vault.to_account_info(),
+                    to: ctx.accounts.payment_token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(DepositedWithNft {
+            config: ctx.accounts.config.key(),
+            user: ctx.accounts.signer.key(),
+            nft_mint: ctx.accounts.nft_account.mint,
+            amount,
+            paid_amount: ctx.accounts.user_token_sale.paid_amount,
+            total_paid_amount: ctx.accounts.config.paid_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn withdraw(ctx: Context<Withdraw>) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
+
+        let config = &mut ctx.accounts.config;
+        // Only settled payments can be withdrawn, the rest of the vault is
+        // owed back to users.
+        let amount = config.paid_amount - config.withdrawn_amount;
+
+        if current_time <= sale_end_time(config) + config.withdraw_cliff {
+            return Err(ErrorCode::CannotWithdrawBeforeSaleEnds.into());
+        }
+        if raised_amount(config) < config.soft_cap {
+            return Err(ErrorCode::SoftCapNotReached.into());
+        }
+        if amount == 0 {
+            return Err(ErrorCode::NoAvailableTokens.into());
+        }
+
+        config.withdrawn_amount += amount;
+
+        let index = (config.withdrawal_count % MAX_WITHDRAWAL_RECORDS as u64) as usize;
+        config.withdrawals[index] = WithdrawalRecord {
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            timestamp: current_time,
+        };
+        config.withdrawal_count += 1;
+
+        // Transfer tokens from the vault to recipient
+        {
+            let seeds = &[
+                ctx.accounts.config.to_account_info().key.as_ref(),
+                &[ctx.accounts.config.nonce],
+            ];
+            let sale_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.payment_token_vault.to_account_info(),
+                    to: ctx.accounts.recipient.to_account_info(),
+                    authority: ctx.accounts.sale_signer.to_account_info(),
+                },
+                sale_signer,
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(Withdrawn {
+            config: ctx.accounts.config.key(),
+            recipient: ctx.accounts.recipient.key(),
+            amount,
+            withdrawn_amount: ctx.accounts.config.withdrawn_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_nft_allocation(
+        ctx: Context<SetNftAllocation>,
+        nft_allocation: u64,
+        nft_wallet_cap: u64,
+        overrides: Vec<NftAllocationOverride>,
+    ) -> Result<()> {
+        if overrides.len() > MAX_NFT_ALLOCATION_OVERRIDES {
+            return Err(ErrorCode::InvalidNftAllocationOverrides.into());
+        }
+
+        let config = &mut ctx.accounts.config;
+        config.nft_allocation = nft_allocation;
+        config.nft_wallet_cap = nft_wallet_cap;
+        config.nft_allocation_overrides = Default::default();
+        for (i, x) in overrides.iter().enumerate() {
+            if x.mint == Pubkey::default() || overrides[..i].iter().any(|y| y.mint == x.mint) {
+                return Err(ErrorCode::InvalidNftAllocationOverrides.into());
+            }
+            config.nft_allocation_overrides[i] = *x;
+        }
+        config.nft_allocation_override_count = overrides.len() as u8;
+
+        emit!(NftAllocationUpdated {
+            config: config.key(),
+            nft_allocation,
+            nft_wallet_cap,
+            override_count: config.nft_allocation_override_count,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_phases(ctx: Context<SetPhases>, phases: Vec<SalePhase>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        let current_time: u64 = sysvar::clock::Clock::get()
+            .unwrap()
+            .unix_timestamp
+            .try_into()
+            .unwrap();
+        if config.start_time <= current_time {
+            return Err(ErrorCode::TokenSaleAlreadyStarted.into());
+        }
+        if phases.len() > MAX_SALE_PHASES
+            || !is_valid_sale_phases(&phases, config.start_time, sale_end_time(config))
+        {
+            return Err(ErrorCode::InvalidSalePhases.into());
+        }
+
+        config.phases = Default::default();
+        for (i, phase) in phases.iter().enumerate() {
+            config.phases[i] = *phase;
+        }
+        config.phase_count = phases.len() as u8;
+
+        emit!(PhasesUpdated {
+            config: config.key(),
+            phase_count: config.phase_count,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_weighted_mode(
+        ctx: Context<SetWeightedMode>,
+        weighted: bool,
+        tier_weights: [u64; STAKING_ALLOCATION_LEN],
+    ) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+
+        let current_time: u64 = sysvar::clock::Clock::get()
+            .unwrap()
+            .unix_timestamp
+            .try_into()
+            .unwrap();
+        if config.start_time <= current_time {
+            return Err(ErrorCode::TokenSaleAlreadyStarted.into());
+        }
+        if weighted && tier_weights.iter().all(|x| *x == 0) {
+            return Err(ErrorCode::InvalidTierWeights.into());
+        }
+
+        config.weighted = weighted;
+        config.tier_weights = tier_weights;
+
+        emit!(WeightedModeUpdated {
+            config: config.key(),
+            weighted,
+            tier_weights,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn commit(ctx: Context<Commit>, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time = clock.unix_timestamp.try_into().unwrap();
+
+        let config = &mut ctx.accounts.config;
+
+        if config.start_time > current_time {
+            return Err(ErrorCode::TokenSaleNotStarted.into());
+        }
+        if is_sale_ended(config, current_time) {
+            return Err(ErrorCode::TokenSaleEnded.into());
+        }
+
+        let user_token_sale = &mut ctx.accounts.user_token_sale;
+
+        let (pool_index, tier) =
+            staking_tier(config, user_token_sale, &ctx.accounts.staking_account)?;
+        let allocation = config.staking_allocations[pool_index][tier as usize];
+        let used_allocation = user_token_sale.used_staking_alloc[pool_index];
+
+        if allocation < used_allocation + amount {
+            return Err(ErrorCode::InsufficientAllocation.into());
+        }
+
+        let weighted_commitment = (amount as u128) * (config.tier_weights[tier as usize] as u128);
+
+        user_token_sale.used_staking_alloc[pool_index] += amount;
+        user_token_sale.committed_amount += amount;
+        user_token_sale.weighted_commitment += weighted_commitment;
+        config.committed_amount += amount;
+        config.weighted_commitment += weighted_commitment;
+
+        // Transfer tokens into the IDO vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.user_vault.to_account_info(),
+                    to: ctx.accounts.payment_token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(Committed {
+            config: config.key(),
+            user: ctx.accounts.signer.key(),
+            staking_pool: ctx.accounts.staking_account.pool,
+            tier,
+            amount,
+            committed_amount: user_token_sale.committed_amount,
+            total_committed_amount: config.committed_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Anyone can settle a commitment once the commit window is closed; the
+    // surplus always goes back to the committing user.
+    pub fn settle(ctx: Context<Settle>) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
+
+        let config = &mut ctx.accounts.config;
+
+        if !is_sale_ended(config, current_time) {
+            return Err(ErrorCode::TokenSaleNotEnded.into());
+        }
+        if raised_amount(config) < config.soft_cap {
+            return Err(ErrorCode::SoftCapNotReached.into());
+        }
+
+        let user_token_sale = &mut ctx.accounts.user_token_sale;
+        let surplus = record_settlement(config, user_token_sale)?;
+        let paid_amount = user_token_sale.paid_amount;
+        let purchased_amount = user_token_sale.purchased_amount;
+
+        // Return the surplus payment to the user
+        if surplus > 0 {
+            let seeds = &[
+                ctx.accounts.config.to_account_info().key.as_ref(),
+                &[ctx.accounts.config.nonce],
+            ];
+            let sale_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.payment_token_vault.to_account_info(),
+                    to: ctx.accounts.user_vault.to_account_info(),
+                    authority: ctx.accounts.sale_signer.to_account_info(),
+                },
+                sale_signer,
+            );
+            token::transfer(cpi_ctx, surplus)?;
+        }
+
+        emit!(Settled {
+            config: ctx.accounts.config.key(),
+            user: ctx.accounts.user_token_sale.user,
+            paid_amount,
+            purchased_amount,
+            refunded_amount: surplus,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Proposing the default address cancels a pending transfer.
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        config.pending_owner = new_owner;
+
+        emit!(OwnershipProposed {
+            config: config.key(),
+            owner: config.owner,
+            pending_owner: new_owner,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_owner = config.owner;
+        config.owner = config.pending_owner;
+        config.pending_owner = Pubkey::default();
+
+        emit!(OwnershipTransferred {
+            config: config.key(),
+            previous_owner,
+            owner: config.owner,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn cancel_sale(ctx: Context<CancelSale>) -> Result<()> {
+        let current_time: u64 = sysvar::clock::Clock::get()
+            .unwrap()
+            .unix_timestamp
+            .try_into()
+            .unwrap();
+
+        let config = &mut ctx.accounts.config;
+
+        if config.withdrawn_amount > 0 {
+            return Err(ErrorCode::CannotCancelAfterWithdrawal.into());
+        }
+        // Vesting claims open once the sale ends, so a cancellation after
+        // that would refund tokens already claimed.
+        if is_sale_ended(config, current_time) {
+            return Err(ErrorCode::CannotCancelAfterSaleEnds.into());
+        }
+
+        config.cancelled = true;
+
+        emit!(SaleCancelled {
+            config: config.key(),
+            paid_amount: config.paid_amount,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn refund(ctx: Context<Refund>) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
+
+        let config = &mut ctx.accounts.config;
+
+        // Refunds are open once the sale is cancelled, or once it has ended
+        // without reaching the soft cap.
+        let failed = is_sale_ended(config, current_time) && raised_amount(config) < config.soft_cap;
+        if !config.cancelled && !failed {
+            return Err(ErrorCode::RefundNotAvailable.into());
+        }
+
+        let user_token_sale = &mut ctx.accounts.user_token_sale;
+        let mut committed_amount = 0;
+        if !user_token_sale.settled {
+            committed_amount = user_token_sale.committed_amount;
+        }
+        let amount = user_token_sale.paid_amount + committed_amount;
+        if amount == 0 {
+            return Err(ErrorCode::NothingToRefund.into());
+        }
+
+        config.paid_amount -= user_token_sale.paid_amount;
+        config.sold_amount -= user_token_sale.purchased_amount;
+        config.committed_amount -= committed_amount;
+        if !user_token_sale.settled {
+            config.weighted_commitment -= user_token_sale.weighted_commitment;
+            user_token_sale.committed_amount = 0;
+            user_token_sale.weighted_commitment = 0;
+        }
+        config.refunded_amount += amount;
+        user_token_sale.paid_amount = 0;
+        user_token_sale.purchased_amount = 0;
+
+        // Transfer tokens from the vault back to the user
+        {
+            let seeds = &[
+                ctx.accounts.config.to_account_info().key.as_ref(),
+                &[ctx.accounts.config.nonce],
+            ];
+            let sale_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.payment_token_vault.to_account_info(),
+                    to: ctx.accounts.user_vault.to_account_info(),
+                    authority: ctx.accounts.sale_signer.to_account_info(),
+                },
+                sale_signer,
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(Refunded {
+            config: ctx.accounts.config.key(),
+            user: ctx.accounts.signer.key(),
+            amount,
+            refunded_amount: ctx.accounts.config.refunded_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const START: u64 = 1_000;
+
+    fn override_sale(mint: Pubkey) -> TokenSaleConfig {
+        let mut config = TokenSaleConfig {
+            price: 1_000_000,
+            sale_token_decimals: 6,
+            amount: 1_000_000,
+            start_time: START,
+            period: 100,
+            nft_allocation: 100,
+            nft_allocation_override_count: 1,
+            ..TokenSaleConfig::default()
+        };
+        config.nft_allocation_overrides[0] = NftAllocationOverride {
+            mint,
+            allocation: 300,
+        };
+        config
+    }
+
+    fn nft(collection: Pubkey) -> NftIdoInfo {
+        NftIdoInfo {
+            mint: Pubkey::new_unique(),
+            collection,
+            ..NftIdoInfo::default()
+        }
+    }
+
+    fn error_name(result: Result<()>) -> String {
+        match result {
+            Err(Error::AnchorError(error)) => error.error_name,
+            _ => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn collection_nft_uses_collection_allocation() {
+        let collection = Pubkey::new_unique();
+        let mut config = override_sale(collection);
+        let mut user_token_sale = UserTokenSale::default();
+        let mut nft_ido_info = nft(collection);
+
+        record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft_ido_info,
+            300,
+            START,
+        )
+        .unwrap();
+        assert_eq!(nft_ido_info.used_allocation, 300);
+        assert_eq!(user_token_sale.used_nft_alloc, 300);
+        assert_eq!(user_token_sale.purchased_amount, 300);
+        assert_eq!(config.paid_amount, 300);
+
+        let result = record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft_ido_info,
+            1,
+            START,
+        );
+        assert_eq!(error_name(result), ErrorCode::InsufficientAllocation.name());
+    }
+
+    #[test]
+    fn mint_whitelisted_nft_uses_mint_allocation() {
+        let mut nft_ido_info = nft(Pubkey::default());
+        let mut config = override_sale(nft_ido_info.mint);
+        let mut user_token_sale = UserTokenSale::default();
+
+        record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft_ido_info,
+            300,
+            START,
+        )
+        .unwrap();
+        let result = record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft_ido_info,
+            1,
+            START,
+        );
+        assert_eq!(error_name(result), ErrorCode::InsufficientAllocation.name());
+    }
+
+    #[test]
+    fn collection_nft_ignores_its_mint_override() {
+        let mut nft_ido_info = nft(Pubkey::new_unique());
+        let mut config = override_sale(nft_ido_info.mint);
+        let mut user_token_sale = UserTokenSale::default();
+
+        let result = record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft_ido_info,
+            101,
+            START,
+        );
+        assert_eq!(error_name(result), ErrorCode::InsufficientAllocation.name());
+    }
+
+    #[test]
+    fn other_collection_nft_uses_default_allocation() {
+        let mut config = override_sale(Pubkey::new_unique());
+        let mut user_token_sale = UserTokenSale::default();
+        let mut nft_ido_info = nft(Pubkey::new_unique());
+
+        let result = record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft_ido_info,
+            101,
+            START,
+        );
+        assert_eq!(error_name(result), ErrorCode::InsufficientAllocation.name());
+    }
+
+    #[test]
+    fn wallet_cap_applies_across_collection_nfts() {
+        let collection = Pubkey::new_unique();
+        let mut config = override_sale(collection);
+        config.nft_wallet_cap = 400;
+        let mut user_token_sale = UserTokenSale::default();
+
+        record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft(collection),
+            300,
+            START,
+        )
+        .unwrap();
+        let result = record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft(collection),
+            101,
+            START,
+        );
+        assert_eq!(error_name(result), ErrorCode::NftWalletCapExceeded.name());
+    }
+
+    #[test]
+    fn collection_nft_deposit_closes_with_the_sale() {
+        let collection = Pubkey::new_unique();
+        let mut config = override_sale(collection);
+        let mut user_token_sale = UserTokenSale::default();
+
+        let result = record_nft_deposit(
+            &mut config,
+            &mut user_token_sale,
+            &mut nft(collection),
+            1,
+            START + 101,
+        );
+        assert_eq!(error_name(result), ErrorCode::TokenSaleEnded.name());
+    }
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+/// Leaf of the whitelist tree for `user` with `allocation`.
+pub fn leaf(user: &Pubkey, allocation: u64) -> [u8; 32] {
+    keccak::hashv(&[user.as_ref(), &allocation.to_le_bytes()]).0
+}
+
+/// Parent of two nodes. Pairs are sorted, so proofs do not carry positions.
+pub fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    if a <= b {
+        keccak::hashv(&[a, b]).0
+    } else {
+        keccak::hashv(&[b, a]).0
+    }
+}
+
+/// Checks `proof` links `leaf` to `root`.
+pub fn verify(proof: &[[u8; 32]], root: &[u8; 32], leaf: [u8; 32]) -> bool {
+    let computed = proof.iter().fold(leaf, |node, x| hash_pair(&node, x));
+
+    computed == *root
+}
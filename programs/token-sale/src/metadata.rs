@@ -0,0 +1,298 @@
+//! Minimal reader for Metaplex token metadata accounts, enough to find the
+//! verified collection of an NFT without depending on the metadata crate.
+
+use crate::error::ErrorCode;
+use anchor_lang::prelude::*;
+
+pub mod mpl_token_metadata {
+    use anchor_lang::declare_id;
+
+    declare_id!("metaqbxxUerdq28cj1RbAWkYQm3ybzjb6a8bt518x1s");
+}
+
+/// Account key of a `MetadataV1` account.
+const METADATA_V1_KEY: u8 = 4;
+
+#[derive(AnchorDeserialize)]
+struct Creator {
+    _address: Pubkey,
+    _verified: bool,
+    _share: u8,
+}
+
+#[derive(AnchorDeserialize)]
+struct Collection {
+    verified: bool,
+    key: Pubkey,
+}
+
+/// Leading fields of the metadata account, in their on-chain order.
+#[derive(AnchorDeserialize)]
+struct Metadata {
+    key: u8,
+    _update_authority: Pubkey,
+    mint: Pubkey,
+    _name: String,
+    _symbol: String,
+    _uri: String,
+    _seller_fee_basis_points: u16,
+    _creators: Option<Vec<Creator>>,
+    _primary_sale_happened: bool,
+    _is_mutable: bool,
+    _edition_nonce: Option<u8>,
+    _token_standard: Option<u8>,
+    collection: Option<Collection>,
+}
+
+/// Returns the verified collection of `mint` from its metadata account.
+pub fn verified_collection(metadata: &AccountInfo, mint: &Pubkey) -> Result<Pubkey> {
+    let (address, _) = Pubkey::find_program_address(
+        &[
+            "metadata".as_bytes(),
+            mpl_token_metadata::ID.as_ref(),
+            mint.as_ref(),
+        ],
+        &mpl_token_metadata::ID,
+    );
+    if *metadata.owner != mpl_token_metadata::ID || metadata.key() != address {
+        return Err(ErrorCode::InvalidNftMetadata.into());
+    }
+
+    let data = metadata.try_borrow_data()?;
+    let metadata = match Metadata::deserialize(&mut &data[..]) {
+        Ok(metadata) => metadata,
+        Err(_) => return Err(ErrorCode::InvalidNftMetadata.into()),
+    };
+    if metadata.key != METADATA_V1_KEY || metadata.mint != *mint {
+        return Err(ErrorCode::InvalidNftMetadata.into());
+    }
+
+    match metadata.collection {
+        Some(collection) if collection.verified => Ok(collection.key),
+        _ => Err(ErrorCode::NftNotInVerifiedCollection.into()),
+    }
+}
+
+/// Checks `mint` is a verified member of `collection`.
+pub fn verify_collection(metadata: &AccountInfo, mint: &Pubkey, collection: &Pubkey) -> Result<()> {
+    if verified_collection(metadata, mint)? != *collection {
+        return Err(ErrorCode::NftNotInVerifiedCollection.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes a `MetadataV1` account the way the metadata program lays it
+    /// out, with padded strings and trailing zeroes.
+    fn metadata_data(mint: &Pubkey, collection: Option<(bool, Pubkey)>) -> Vec<u8> {
+        fn put_padded_string(data: &mut Vec<u8>, value: &str, len: usize) {
+            data.extend_from_slice(&(len as u32).to_le_bytes());
+            data.extend_from_slice(value.as_bytes());
+            data.resize(data.len() + len - value.len(), 0);
+        }
+
+        let mut data = vec![METADATA_V1_KEY];
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(mint.as_ref());
+        put_padded_string(&mut data, "Fcon #1", 32);
+        put_padded_string(&mut data, "FCON", 10);
+        put_padded_string(&mut data, "https://example.com/1.json", 200);
+        data.extend_from_slice(&500u16.to_le_bytes());
+        // One verified creator with the full share.
+        data.push(1);
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(Pubkey::new_unique().as_ref());
+        data.extend_from_slice(&[1, 100]);
+        // Primary sale happened, mutable, edition nonce and token standard.
+        data.extend_from_slice(&[1, 1, 1, 254, 1, 0]);
+        match collection {
+            Some((verified, key)) => {
+                data.extend_from_slice(&[1, verified as u8]);
+                data.extend_from_slice(key.as_ref());
+            }
+            None => data.push(0),
+        }
+        // No uses.
+        data.push(0);
+        data.resize(679, 0);
+        data
+    }
+
+    fn metadata_address(mint: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(
+            &[
+                "metadata".as_bytes(),
+                mpl_token_metadata::ID.as_ref(),
+                mint.as_ref(),
+            ],
+            &mpl_token_metadata::ID,
+        )
+        .0
+    }
+
+    fn check(
+        address: Pubkey,
+        owner: Pubkey,
+        mut data: Vec<u8>,
+        mint: &Pubkey,
+        collection: &Pubkey,
+    ) -> Result<()> {
+        let mut lamports = 0;
+        let metadata = AccountInfo::new(
+            &address,
+            false,
+            false,
+            &mut lamports,
+            &mut data,
+            &owner,
+            false,
+            0,
+        );
+        verify_collection(&metadata, mint, collection)
+    }
+
+    fn error_name(result: Result<()>) -> String {
+        match result {
+            Err(Error::AnchorError(error)) => error.error_name,
+            _ => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn verified_collection_member() {
+        let mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+        let data = metadata_data(&mint, Some((true, collection)));
+
+        assert!(check(
+            metadata_address(&mint),
+            mpl_token_metadata::ID,
+            data,
+            &mint,
+            &collection
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn unverified_collection() {
+        let mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+
+        for data in [
+            metadata_data(&mint, Some((false, collection))),
+            metadata_data(&mint, None),
+        ] {
+            let result = check(
+                metadata_address(&mint),
+                mpl_token_metadata::ID,
+                data,
+                &mint,
+                &collection,
+            );
+            assert_eq!(
+                error_name(result),
+                ErrorCode::NftNotInVerifiedCollection.name()
+            );
+        }
+    }
+
+    #[test]
+    fn wrong_collection() {
+        let mint = Pubkey::new_unique();
+        let data = metadata_data(&mint, Some((true, Pubkey::new_unique())));
+
+        let result = check(
+            metadata_address(&mint),
+            mpl_token_metadata::ID,
+            data,
+            &mint,
+            &Pubkey::new_unique(),
+        );
+        assert_eq!(
+            error_name(result),
+            ErrorCode::NftNotInVerifiedCollection.name()
+        );
+    }
+
+    #[test]
+    fn wrong_mint() {
+        let mint = Pubkey::new_unique();
+        let other_mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+
+        // Metadata of another mint at the expected address.
+        let result = check(
+            metadata_address(&mint),
+            mpl_token_metadata::ID,
+            metadata_data(&other_mint, Some((true, collection))),
+            &mint,
+            &collection,
+        );
+        assert_eq!(error_name(result), ErrorCode::InvalidNftMetadata.name());
+
+        // Metadata account of another mint.
+        let result = check(
+            metadata_address(&other_mint),
+            mpl_token_metadata::ID,
+            metadata_data(&other_mint, Some((true, collection))),
+            &mint,
+            &collection,
+        );
+        assert_eq!(error_name(result), ErrorCode::InvalidNftMetadata.name());
+    }
+
+    #[test]
+    fn wrong_owner_or_address() {
+        let mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+
+        let result = check(
+            metadata_address(&mint),
+            Pubkey::new_unique(),
+            metadata_data(&mint, Some((true, collection))),
+            &mint,
+            &collection,
+        );
+        assert_eq!(error_name(result), ErrorCode::InvalidNftMetadata.name());
+
+        let result = check(
+            Pubkey::new_unique(),
+            mpl_token_metadata::ID,
+            metadata_data(&mint, Some((true, collection))),
+            &mint,
+            &collection,
+        );
+        assert_eq!(error_name(result), ErrorCode::InvalidNftMetadata.name());
+    }
+
+    #[test]
+    fn invalid_data() {
+        let mint = Pubkey::new_unique();
+        let collection = Pubkey::new_unique();
+
+        let mut data = metadata_data(&mint, Some((true, collection)));
+        data[0] = 0;
+        let result = check(
+            metadata_address(&mint),
+            mpl_token_metadata::ID,
+            data,
+            &mint,
+            &collection,
+        );
+        assert_eq!(error_name(result), ErrorCode::InvalidNftMetadata.name());
+
+        let result = check(
+            metadata_address(&mint),
+            mpl_token_metadata::ID,
+            metadata_data(&mint, Some((true, collection)))[..100].to_vec(),
+            &mint,
+            &collection,
+        );
+        assert_eq!(error_name(result), ErrorCode::InvalidNftMetadata.name());
+    }
+}
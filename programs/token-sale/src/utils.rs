@@ -0,0 +1,51 @@
+use crate::account::SalePhase;
+use crate::constants::{
+    BPS_DENOMINATOR, DEPOSIT_PATH_NFT, DEPOSIT_PATH_STAKING, DEPOSIT_PATH_WHITELIST,
+};
+use std::convert::TryFrom;
+
+/// Converts a payment amount into sale tokens at `price` payment base units
+/// per whole sale token. Rounds down, so any dust stays with the sale.
+pub fn get_purchase_amount(
+    payment_amount: u64,
+    price: u64,
+    sale_token_decimals: u8,
+) -> Option<u64> {
+    let one_token = 10u128.checked_pow(sale_token_decimals as u32)?;
+    let amount = (payment_amount as u128)
+        .checked_mul(one_token)?
+        .checked_div(price as u128)?;
+
+    u64::try_from(amount).ok()
+}
+
+pub fn apply_multiplier(allocation: u64, multiplier_bps: u64) -> u64 {
+    let allocation = (allocation as u128) * (multiplier_bps as u128) / (BPS_DENOMINATOR as u128);
+
+    u64::try_from(allocation).unwrap_or(u64::MAX)
+}
+
+/// Checks phases are ordered, do not overlap and stay within the sale window.
+pub fn is_valid_sale_phases(phases: &[SalePhase], start_time: u64, end_time: u64) -> bool {
+    let all_paths = DEPOSIT_PATH_STAKING | DEPOSIT_PATH_WHITELIST | DEPOSIT_PATH_NFT;
+    let mut previous_end: Option<u64> = None;
+    for phase in phases {
+        if phase.start_time < start_time
+            || phase.end_time > end_time
+            || phase.start_time > phase.end_time
+            || phase.deposit_paths == 0
+            || phase.deposit_paths & !all_paths != 0
+            || phase.allocation_multiplier_bps == 0
+        {
+            return false;
+        }
+        if let Some(end) = previous_end {
+            if phase.start_time <= end {
+                return false;
+            }
+        }
+        previous_end = Some(phase.end_time);
+    }
+
+    true
+}
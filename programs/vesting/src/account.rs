@@ -1,37 +1,75 @@
-use anchor_lang::prelude::*;
-
-#[account]
-#[derive(Default)]
-pub struct Vesting {
-    /// Nonce to derive the program-derived address owning the vaults.
-    pub nonce: u8,
-    /// IDO config address
-    pub ido_config: Pubkey,
-    /// Mint of the vesting token
-    pub token_mint: Pubkey,
-    /// Vault to store vesting tokens.
-    pub token_vault: Pubkey,
-    /// start time
-    pub start_time: u64,
-    /// vesting period
-    pub period: u64,
-    /// The period which can claim
-    pub claim_period: u64,
-    /// Percentage to be claimed at TGE
-    pub tge_pct: u64,
-    /// Total vesting amount (TGE amount is inclued in amount)
-    pub amount: u64,
-    /// Withdrawn amount for unallocated funds
-    pub withdrawn_amount: u64,
-}
-
-#[account]
-#[derive(Default)]
-pub struct UserVestingAccount {
-    /// Vesting address
-    pub vesting: Pubkey,
-    /// Nonce to derive the program-derived address owning the vaults.
-    pub nonce: u8,
-    /// claimed amount
-    pub claimed: u64,
-}
+use crate::MAX_MILESTONES;
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(Default)]
+pub struct Vesting {
+    /// Nonce to derive the program-derived address owning the vaults.
+    pub nonce: u8,
+    /// Vesting creator
+    pub authority: Pubkey,
+    /// IDO config address, unset for grant vestings
+    pub ido_config: Pubkey,
+    /// Whether user amounts are granted by the authority instead of purchased
+    pub grant: bool,
+    /// Whether the authority can revoke grants
+    pub revocable: bool,
+    /// Mint of the vesting token
+    pub token_mint: Pubkey,
+    /// Vault to store vesting tokens.
+    pub token_vault: Pubkey,
+    /// start time
+    pub start_time: u64,
+    /// vesting period
+    pub period: u64,
+    /// Duration after start time before linear vesting begins, within period
+    pub cliff: u64,
+    /// The period which can claim
+    pub claim_period: u64,
+    /// Percentage to be claimed at TGE
+    pub tge_pct: u64,
+    /// Total vesting amount (TGE amount is inclued in amount), total granted
+    /// amount for grant vestings
+    pub amount: u64,
+    /// Withdrawn amount for unallocated funds
+    pub withdrawn_amount: u64,
+    /// Total amount claimed by all users
+    pub claimed_amount: u64,
+    /// Number of milestones, the linear schedule is used if zero
+    pub milestone_count: u8,
+    /// Ordered milestones replacing TGE and linear vesting
+    pub milestones: [Milestone; MAX_MILESTONES],
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct Milestone {
+    /// Unlock time
+    pub timestamp: u64,
+    /// Unlocked basis points of the user amount at this milestone, cumulative
+    pub cumulative_bps: u64,
+}
+
+#[account]
+#[derive(Default)]
+pub struct UserVestingAccount {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Beneficiary address
+    pub beneficiary: Pubkey,
+    /// Nonce to derive the program-derived address owning the vaults.
+    pub nonce: u8,
+    /// Granted amount, unused for sale vestings
+    pub amount: u64,
+    /// claimed amount
+    pub claimed: u64,
+    /// Revocation time freezing the schedule, not revoked if zero
+    pub revoked_at: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default)]
+pub struct GrantEntry {
+    /// Beneficiary address
+    pub beneficiary: Pubkey,
+    /// Granted amount
+    pub amount: u64,
+}
@@ -1,23 +1,51 @@
-use anchor_lang::prelude::*;
-
-#[error_code]
-pub enum ErrorCode {
-    #[msg("Amount must be greater than zero.")]
-    AmountMustBeGreaterThanZero,
-    #[msg("Period must be greater than zero.")]
-    PeriodMustBeGreaterThanZero,
-    #[msg("Claim period cannot be greater than period.")]
-    ClaimPeriodCannotBeGreaterThanPeriod,
-    #[msg("Tge percentage cannot be greater than 100%.")]
-    TgePctCannotBeGreater100,
-    #[msg("Start time must be greater than zero.")]
-    StartTimeMustBeGreaterThanZero,
-    #[msg("Cannot claim before start time.")]
-    CannotClaimBeforeStartTime,
-    #[msg("No claimable tokens.")]
-    NoClaimableTokens,
-    #[msg("Cannot withdraw before IDO ends.")]
-    CannotWithdrawBeforeIdoEnds,
-    #[msg("Already withdrawn.")]
-    AlreadyWithdrawn,
-}
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Amount must be greater than zero.")]
+    AmountMustBeGreaterThanZero,
+    #[msg("Period must be greater than zero.")]
+    PeriodMustBeGreaterThanZero,
+    #[msg("Claim period cannot be greater than period.")]
+    ClaimPeriodCannotBeGreaterThanPeriod,
+    #[msg("Tge percentage cannot be greater than 100%.")]
+    TgePctCannotBeGreater100,
+    #[msg("Start time must be greater than zero.")]
+    StartTimeMustBeGreaterThanZero,
+    #[msg("Cannot claim before start time.")]
+    CannotClaimBeforeStartTime,
+    #[msg("No claimable tokens.")]
+    NoClaimableTokens,
+    #[msg("Cannot withdraw before IDO ends.")]
+    CannotWithdrawBeforeIdoEnds,
+    #[msg("Already withdrawn.")]
+    AlreadyWithdrawn,
+    #[msg("Token decimals must match the sale token decimals.")]
+    InvalidTokenDecimals,
+    #[msg("No unallocated tokens.")]
+    NoUnallocatedTokens,
+    #[msg("Cliff must be shorter than period.")]
+    CliffMustBeShorterThanPeriod,
+    #[msg("Milestones must be ordered and unlock 100% at the last one.")]
+    InvalidMilestones,
+    #[msg("Vesting is not in grant mode.")]
+    NotGrantVesting,
+    #[msg("Remaining accounts do not match the batch.")]
+    InvalidBatchAccounts,
+    #[msg("Invalid vesting account.")]
+    InvalidVestingAccount,
+    #[msg("Vesting is not revocable.")]
+    NotRevocable,
+    #[msg("Grant already revoked.")]
+    GrantRevoked,
+    #[msg("Math overflow.")]
+    MathOverflow,
+    #[msg("No dust to sweep.")]
+    NoDust,
+    #[msg("Token sale cancelled.")]
+    TokenSaleCancelled,
+    #[msg("Soft cap not reached.")]
+    SoftCapNotReached,
+    #[msg("Cannot claim before token sale ends.")]
+    CannotClaimBeforeIdoEnds,
+}
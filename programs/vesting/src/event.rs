@@ -0,0 +1,85 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct Claimed {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Claimer address
+    pub user: Pubkey,
+    /// Claimed amount
+    pub amount: u64,
+    /// Total claimed amount after the claim
+    pub claimed: u64,
+    /// Total amount vested to the user
+    pub user_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Granted {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Beneficiary address
+    pub beneficiary: Pubkey,
+    /// Granted amount
+    pub amount: u64,
+    /// Total amount granted to the beneficiary after the grant
+    pub user_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Revoked {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Beneficiary address
+    pub beneficiary: Pubkey,
+    /// Amount vested at revocation, still claimable by the beneficiary
+    pub vested_amount: u64,
+    /// Unvested amount returned to the treasury
+    pub returned_amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DustSwept {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Token account receiving the dust
+    pub recipient: Pubkey,
+    /// Swept amount
+    pub amount: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VestingInitialized {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Vesting creator
+    pub authority: Pubkey,
+    /// IDO config address, unset for grant vestings
+    pub ido_config: Pubkey,
+    /// Mint of the vesting token
+    pub token_mint: Pubkey,
+    /// Whether user amounts are granted by the authority instead of purchased
+    pub grant: bool,
+    /// Deposited vesting amount, zero for grant vestings
+    pub amount: u64,
+    /// Vesting start time
+    pub start_time: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct UnallocatedWithdrawn {
+    /// Vesting address
+    pub vesting: Pubkey,
+    /// Token account receiving the unallocated tokens
+    pub recipient: Pubkey,
+    /// Withdrawn amount
+    pub amount: u64,
+    /// Total unallocated amount withdrawn after the withdrawal
+    pub withdrawn_amount: u64,
+    pub timestamp: i64,
+}
@@ -1,23 +1,33 @@
 pub mod account;
 pub mod context;
 pub mod error;
+pub mod event;
+pub mod schedule;
 
+use account::{GrantEntry, Milestone, UserVestingAccount};
 use anchor_lang::prelude::*;
 use anchor_lang::solana_program::sysvar;
 use anchor_spl::token::{self};
 use context::*;
 use error::ErrorCode;
+use event::*;
+use program_utils::init_or_load;
+use schedule::*;
 use std::convert::Into;
 use std::convert::TryInto;
+use token_sale::{allocated_sale_amount, is_sale_ended};
 
 declare_id!("2btf863eSGk6bhBTm4GQ628uBsHJdfS86iWkDA9GfqzB");
 
 pub const DENOMINATOR: u64 = 100000;
+pub const BPS_DENOMINATOR: u64 = 10_000;
+pub const MAX_MILESTONES: usize = 32;
 
 #[program]
 pub mod vesting {
     use super::*;
 
+    #[allow(clippy::too_many_arguments)]
     pub fn initialize_vesting(
         ctx: Context<InitializeVesting>,
         amount: u64,
@@ -25,22 +35,21 @@ pub mod vesting {
         start_time: u64,
         period: u64,
         claim_period: u64,
+        cliff: u64,
+        milestones: Vec<Milestone>,
     ) -> Result<()> {
         if amount == 0 {
             return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
         }
-        if start_time == 0 {
-            return Err(ErrorCode::StartTimeMustBeGreaterThanZero.into());
-        }
-        if period == 0 || claim_period == 0 {
-            return Err(ErrorCode::PeriodMustBeGreaterThanZero.into());
-        }
-        if claim_period > period {
-            return Err(ErrorCode::ClaimPeriodCannotBeGreaterThanPeriod.into());
-        }
-        if tge_pct > DENOMINATOR {
-            return Err(ErrorCode::TgePctCannotBeGreater100.into());
-        }
+        validate_schedule(
+            start_time,
+            tge_pct,
+            period,
+            claim_period,
+            cliff,
+            &milestones,
+        )?;
+
         // Transfer tokens into the vault.
         {
             let cpi_ctx = CpiContext::new(
@@ -55,16 +64,36 @@ pub mod vesting {
         }
 
         let vesting = &mut ctx.accounts.vesting;
+        vesting.authority = ctx.accounts.signer.key();
         vesting.ido_config = ctx.accounts.ido_config.key();
+        vesting.grant = false;
+        vesting.revocable = false;
         vesting.token_mint = ctx.accounts.token_mint.key();
         vesting.token_vault = ctx.accounts.token_vault.key();
         vesting.start_time = start_time;
         vesting.period = period;
+        vesting.cliff = cliff;
         vesting.amount = amount;
         vesting.claim_period = claim_period;
         vesting.tge_pct = tge_pct;
         vesting.nonce = *ctx.bumps.get("vesting_signer").unwrap();
         vesting.withdrawn_amount = 0;
+        vesting.claimed_amount = 0;
+        for (i, milestone) in milestones.iter().enumerate() {
+            vesting.milestones[i] = *milestone;
+        }
+        vesting.milestone_count = milestones.len() as u8;
+
+        emit!(VestingInitialized {
+            vesting: vesting.key(),
+            authority: vesting.authority,
+            ido_config: vesting.ido_config,
+            token_mint: vesting.token_mint,
+            grant: vesting.grant,
+            amount: vesting.amount,
+            start_time,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
 
         Ok(())
     }
@@ -72,7 +101,10 @@ pub mod vesting {
     pub fn create_vesting_account(ctx: Context<CreateVestingAccount>) -> Result<()> {
         let user_vesting = &mut ctx.accounts.user_vesting;
         user_vesting.vesting = ctx.accounts.vesting.key();
+        user_vesting.beneficiary = ctx.accounts.signer.key();
+        user_vesting.amount = 0;
         user_vesting.claimed = 0;
+        user_vesting.revoked_at = 0;
         user_vesting.nonce = *ctx.bumps.get("user_vesting").unwrap();
 
         Ok(())
@@ -87,19 +119,29 @@ pub mod vesting {
             .try_into()
             .unwrap();
 
-        if ido_config.start_time + ido_config.period > current_time {
+        if !is_sale_ended(ido_config, current_time) {
             return Err(ErrorCode::CannotWithdrawBeforeIdoEnds.into());
         }
 
         let vesting = &mut ctx.accounts.vesting;
 
-        if vesting.withdrawn_amount > 0 {
-            return Err(ErrorCode::AlreadyWithdrawn.into());
+        // Weighted sales only record sold tokens as commitments settle, so
+        // keep what every commitment can settle to until they all have, then
+        // the rounding they left can be withdrawn too.
+        let allocated_amount = allocated_sale_amount(ido_config).ok_or(ErrorCode::MathOverflow)?;
+        let amount =
+            unallocated_amount(vesting, allocated_amount).ok_or(ErrorCode::MathOverflow)?;
+        if amount == 0 {
+            if vesting.withdrawn_amount > 0 {
+                return Err(ErrorCode::AlreadyWithdrawn.into());
+            }
+            return Err(ErrorCode::NoUnallocatedTokens.into());
         }
 
-        let required_amount = vesting.amount * ido_config.paid_amount / ido_config.amount;
-
-        vesting.withdrawn_amount = vesting.amount - required_amount;
+        vesting.withdrawn_amount = vesting
+            .withdrawn_amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
 
         // Transfer tokens from the vault to creator
 
@@ -115,36 +157,269 @@ pub mod vesting {
             },
             vesting_signer,
         );
-        token::transfer(cpi_ctx, vesting.withdrawn_amount)?;
+        token::transfer(cpi_ctx, amount)?;
+
+        emit!(UnallocatedWithdrawn {
+            vesting: vesting.key(),
+            recipient: ctx.accounts.recipient_vault.key(),
+            amount,
+            withdrawn_amount: vesting.withdrawn_amount,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
 
         Ok(())
     }
 
     pub fn claim(ctx: Context<Claim>) -> Result<()> {
-        let vesting = &ctx.accounts.vesting;
-        let current_time: u64 = sysvar::clock::Clock::get()
-            .unwrap()
-            .unix_timestamp
-            .try_into()
-            .unwrap();
+        let vesting = &mut ctx.accounts.vesting;
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
 
         if vesting.start_time > current_time {
             return Err(ErrorCode::CannotClaimBeforeStartTime.into());
         }
 
-        let user_amount = vesting.amount * ctx.accounts.user_token_sale.paid_amount
-            / ctx.accounts.ido_config.amount;
+        // Claims only open once the sale can no longer be refunded.
+        if !is_sale_ended(&ctx.accounts.ido_config, current_time) {
+            return Err(ErrorCode::CannotClaimBeforeIdoEnds.into());
+        }
+
+        let user_amount = ctx.accounts.user_token_sale.purchased_amount;
+        let total_claimable =
+            unlocked_amount(vesting, user_amount, current_time).ok_or(ErrorCode::MathOverflow)?;
+
+        let user_vesting = &mut ctx.accounts.user_vesting;
+        if total_claimable <= user_vesting.claimed {
+            return Err(ErrorCode::NoClaimableTokens.into());
+        }
+
+        let claimable = total_claimable - user_vesting.claimed;
+        user_vesting.claimed = total_claimable;
+        vesting.claimed_amount = vesting
+            .claimed_amount
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Transfer tokens from the vault to user
+        {
+            let seeds = &[vesting.to_account_info().key.as_ref(), &[vesting.nonce]];
+            let vesting_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.token_vault.to_account_info(),
+                    to: ctx.accounts.claim_recipient.to_account_info(),
+                    authority: ctx.accounts.vesting_signer.to_account_info(),
+                },
+                vesting_signer,
+            );
+            token::transfer(cpi_ctx, claimable)?;
+        }
+
+        emit!(Claimed {
+            vesting: vesting.key(),
+            user: ctx.accounts.signer.key(),
+            amount: claimable,
+            claimed: user_vesting.claimed,
+            user_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn initialize_grant_vesting(
+        ctx: Context<InitializeGrantVesting>,
+        tge_pct: u64,
+        start_time: u64,
+        period: u64,
+        claim_period: u64,
+        cliff: u64,
+        milestones: Vec<Milestone>,
+        revocable: bool,
+    ) -> Result<()> {
+        validate_schedule(
+            start_time,
+            tge_pct,
+            period,
+            claim_period,
+            cliff,
+            &milestones,
+        )?;
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.authority = ctx.accounts.signer.key();
+        vesting.ido_config = Pubkey::default();
+        vesting.grant = true;
+        vesting.revocable = revocable;
+        vesting.token_mint = ctx.accounts.token_mint.key();
+        vesting.token_vault = ctx.accounts.token_vault.key();
+        vesting.start_time = start_time;
+        vesting.period = period;
+        vesting.cliff = cliff;
+        vesting.amount = 0;
+        vesting.claim_period = claim_period;
+        vesting.tge_pct = tge_pct;
+        vesting.nonce = *ctx.bumps.get("vesting_signer").unwrap();
+        vesting.withdrawn_amount = 0;
+        vesting.claimed_amount = 0;
+        for (i, milestone) in milestones.iter().enumerate() {
+            vesting.milestones[i] = *milestone;
+        }
+        vesting.milestone_count = milestones.len() as u8;
+
+        emit!(VestingInitialized {
+            vesting: vesting.key(),
+            authority: vesting.authority,
+            ido_config: vesting.ido_config,
+            token_mint: vesting.token_mint,
+            grant: vesting.grant,
+            amount: vesting.amount,
+            start_time,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn grant(ctx: Context<Grant>, beneficiary: Pubkey, amount: u64) -> Result<()> {
+        if amount == 0 {
+            return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+        }
+
+        // Transfer tokens into the vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.fund_vault.to_account_info(),
+                    to: ctx.accounts.token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.amount = vesting
+            .amount
+            .checked_add(amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        let user_vesting = &mut ctx.accounts.user_vesting;
+        user_vesting.vesting = vesting.key();
+        user_vesting.beneficiary = beneficiary;
+        user_vesting.amount = amount;
+        user_vesting.claimed = 0;
+        user_vesting.revoked_at = 0;
+        user_vesting.nonce = *ctx.bumps.get("user_vesting").unwrap();
+
+        emit!(Granted {
+            vesting: vesting.key(),
+            beneficiary,
+            amount,
+            user_amount: user_vesting.amount,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Grants to many beneficiaries, creating their vesting accounts passed
+    /// as remaining accounts or adding to existing grants.
+    pub fn batch_grant<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchGrant<'info>>,
+        grants: Vec<GrantEntry>,
+    ) -> Result<()> {
+        if grants.is_empty() || grants.len() != ctx.remaining_accounts.len() {
+            return Err(ErrorCode::InvalidBatchAccounts.into());
+        }
+
+        let vesting_key = ctx.accounts.vesting.key();
+        let timestamp = sysvar::clock::Clock::get().unwrap().unix_timestamp;
+        let mut total_amount: u64 = 0;
+        for (grant, info) in grants.iter().zip(ctx.remaining_accounts.iter()) {
+            if grant.amount == 0 {
+                return Err(ErrorCode::AmountMustBeGreaterThanZero.into());
+            }
+
+            let (address, bump) = Pubkey::find_program_address(
+                &[vesting_key.as_ref(), grant.beneficiary.as_ref()],
+                ctx.program_id,
+            );
+            if *info.key != address {
+                return Err(ErrorCode::InvalidVestingAccount.into());
+            }
+
+            let mut user_vesting: Account<UserVestingAccount> = init_or_load(
+                info,
+                &[vesting_key.as_ref(), grant.beneficiary.as_ref(), &[bump]],
+                &ctx.accounts.signer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            if user_vesting.revoked_at > 0 {
+                return Err(ErrorCode::GrantRevoked.into());
+            }
+            user_vesting.vesting = vesting_key;
+            user_vesting.beneficiary = grant.beneficiary;
+            user_vesting.amount = user_vesting
+                .amount
+                .checked_add(grant.amount)
+                .ok_or(ErrorCode::MathOverflow)?;
+            user_vesting.nonce = bump;
+            user_vesting.exit(ctx.program_id)?;
 
-        let tge_amount = user_amount * vesting.tge_pct / DENOMINATOR;
+            total_amount = total_amount
+                .checked_add(grant.amount)
+                .ok_or(ErrorCode::MathOverflow)?;
 
-        let vested_amount = (user_amount - tge_amount)
-            * ((current_time - vesting.start_time) / vesting.claim_period * vesting.claim_period)
-            / vesting.period;
-        let mut total_claimable = vested_amount + tge_amount;
-        if total_claimable > user_amount {
-            total_claimable = user_amount;
+            emit!(Granted {
+                vesting: vesting_key,
+                beneficiary: grant.beneficiary,
+                amount: grant.amount,
+                user_amount: user_vesting.amount,
+                timestamp,
+            });
         }
 
+        // Transfer tokens into the vault.
+        {
+            let cpi_ctx = CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.fund_vault.to_account_info(),
+                    to: ctx.accounts.token_vault.to_account_info(),
+                    authority: ctx.accounts.signer.to_account_info(),
+                },
+            );
+            token::transfer(cpi_ctx, total_amount)?;
+        }
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.amount = vesting
+            .amount
+            .checked_add(total_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        Ok(())
+    }
+
+    pub fn claim_grant(ctx: Context<ClaimGrant>) -> Result<()> {
+        let vesting = &mut ctx.accounts.vesting;
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
+
+        if vesting.start_time > current_time {
+            return Err(ErrorCode::CannotClaimBeforeStartTime.into());
+        }
+
+        let user_amount = ctx.accounts.user_vesting.amount;
+        let total_claimable =
+            granted_vested_amount(vesting, &ctx.accounts.user_vesting, current_time)
+                .ok_or(ErrorCode::MathOverflow)?;
+
         let user_vesting = &mut ctx.accounts.user_vesting;
         if total_claimable <= user_vesting.claimed {
             return Err(ErrorCode::NoClaimableTokens.into());
@@ -152,6 +427,10 @@ pub mod vesting {
 
         let claimable = total_claimable - user_vesting.claimed;
         user_vesting.claimed = total_claimable;
+        vesting.claimed_amount = vesting
+            .claimed_amount
+            .checked_add(claimable)
+            .ok_or(ErrorCode::MathOverflow)?;
 
         // Transfer tokens from the vault to user
         {
@@ -170,6 +449,102 @@ pub mod vesting {
             token::transfer(cpi_ctx, claimable)?;
         }
 
+        emit!(Claimed {
+            vesting: vesting.key(),
+            user: ctx.accounts.signer.key(),
+            amount: claimable,
+            claimed: user_vesting.claimed,
+            user_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Freezes a grant at the current time. Vested tokens stay claimable by
+    /// the beneficiary and the unvested remainder returns to the treasury.
+    pub fn revoke(ctx: Context<Revoke>) -> Result<()> {
+        let clock = sysvar::clock::Clock::get().unwrap();
+        let current_time: u64 = clock.unix_timestamp.try_into().unwrap();
+
+        let vested_amount = granted_vested_amount(
+            &ctx.accounts.vesting,
+            &ctx.accounts.user_vesting,
+            current_time,
+        )
+        .ok_or(ErrorCode::MathOverflow)?;
+        let user_vesting = &mut ctx.accounts.user_vesting;
+        let returned_amount = user_vesting.amount - vested_amount;
+        user_vesting.revoked_at = current_time;
+
+        let vesting = &mut ctx.accounts.vesting;
+        vesting.amount = vesting
+            .amount
+            .checked_sub(returned_amount)
+            .ok_or(ErrorCode::MathOverflow)?;
+
+        // Transfer unvested tokens from the vault to the treasury
+        if returned_amount > 0 {
+            let seeds = &[vesting.to_account_info().key.as_ref(), &[vesting.nonce]];
+            let vesting_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.token_vault.to_account_info(),
+                    to: ctx.accounts.treasury.to_account_info(),
+                    authority: ctx.accounts.vesting_signer.to_account_info(),
+                },
+                vesting_signer,
+            );
+            token::transfer(cpi_ctx, returned_amount)?;
+        }
+
+        emit!(Revoked {
+            vesting: vesting.key(),
+            beneficiary: user_vesting.beneficiary,
+            vested_amount,
+            returned_amount,
+            timestamp: clock.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    /// Sends vault tokens above what is still owed to users, such as
+    /// rounding dust or direct transfers, to the vesting authority.
+    pub fn sweep_dust(ctx: Context<SweepDust>) -> Result<()> {
+        let vesting = &ctx.accounts.vesting;
+        let outstanding = outstanding_amount(vesting).ok_or(ErrorCode::MathOverflow)?;
+        if ctx.accounts.token_vault.amount <= outstanding {
+            return Err(ErrorCode::NoDust.into());
+        }
+        let amount = ctx.accounts.token_vault.amount - outstanding;
+
+        // Transfer dust from the vault to the authority
+        {
+            let seeds = &[vesting.to_account_info().key.as_ref(), &[vesting.nonce]];
+            let vesting_signer = &[&seeds[..]];
+
+            let cpi_ctx = CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                token::Transfer {
+                    from: ctx.accounts.token_vault.to_account_info(),
+                    to: ctx.accounts.dust_recipient.to_account_info(),
+                    authority: ctx.accounts.vesting_signer.to_account_info(),
+                },
+                vesting_signer,
+            );
+            token::transfer(cpi_ctx, amount)?;
+        }
+
+        emit!(DustSwept {
+            vesting: vesting.key(),
+            recipient: ctx.accounts.dust_recipient.key(),
+            amount,
+            timestamp: sysvar::clock::Clock::get().unwrap().unix_timestamp,
+        });
+
         Ok(())
     }
 }
@@ -0,0 +1,432 @@
+//! Vesting schedule math.
+//!
+//! Amounts are multiplied in u128 and every step is checked, so a schedule
+//! over any u64 amount either computes exactly or fails with `MathOverflow`.
+//!
+//! Rounding policy: every division rounds down, so an intermediate unlock
+//! never exceeds the exact entitlement and the vault always covers what is
+//! owed. Once the schedule ends the unlocked amount is the full user amount,
+//! so the last claim releases whatever was rounded away before it. Tokens
+//! left in the vault above outstanding entitlements are dust and can only be
+//! swept to the vesting authority.
+
+use crate::account::{Milestone, UserVestingAccount, Vesting};
+use crate::error::ErrorCode;
+use crate::{BPS_DENOMINATOR, DENOMINATOR, MAX_MILESTONES};
+use anchor_lang::prelude::*;
+use std::convert::TryFrom;
+
+/// `amount * numerator / denominator` rounded down, `None` if the result
+/// does not fit in u64 or the denominator is zero.
+pub fn mul_div(amount: u64, numerator: u64, denominator: u64) -> Option<u64> {
+    let result = (amount as u128)
+        .checked_mul(numerator as u128)?
+        .checked_div(denominator as u128)?;
+    u64::try_from(result).ok()
+}
+
+/// Checks milestones start at or after `start_time`, are strictly ordered and
+/// unlock everything at the last one.
+pub fn is_valid_milestones(milestones: &[Milestone], start_time: u64) -> bool {
+    let mut previous: Option<&Milestone> = None;
+    for milestone in milestones {
+        if milestone.timestamp < start_time || milestone.cumulative_bps > BPS_DENOMINATOR {
+            return false;
+        }
+        if let Some(previous) = previous {
+            if milestone.timestamp <= previous.timestamp
+                || milestone.cumulative_bps < previous.cumulative_bps
+            {
+                return false;
+            }
+        }
+        previous = Some(milestone);
+    }
+
+    match previous {
+        Some(last) => last.cumulative_bps == BPS_DENOMINATOR,
+        None => false,
+    }
+}
+
+/// Checks schedule parameters shared by sale and grant vestings. A milestone
+/// schedule replaces TGE and linear vesting parameters.
+pub fn validate_schedule(
+    start_time: u64,
+    tge_pct: u64,
+    period: u64,
+    claim_period: u64,
+    cliff: u64,
+    milestones: &[Milestone],
+) -> Result<()> {
+    if start_time == 0 {
+        return Err(ErrorCode::StartTimeMustBeGreaterThanZero.into());
+    }
+    if !milestones.is_empty() {
+        if milestones.len() > MAX_MILESTONES || !is_valid_milestones(milestones, start_time) {
+            return Err(ErrorCode::InvalidMilestones.into());
+        }
+    } else {
+        if period == 0 || claim_period == 0 {
+            return Err(ErrorCode::PeriodMustBeGreaterThanZero.into());
+        }
+        if cliff >= period {
+            return Err(ErrorCode::CliffMustBeShorterThanPeriod.into());
+        }
+        if claim_period > period - cliff {
+            return Err(ErrorCode::ClaimPeriodCannotBeGreaterThanPeriod.into());
+        }
+        if tge_pct > DENOMINATOR {
+            return Err(ErrorCode::TgePctCannotBeGreater100.into());
+        }
+        if start_time.checked_add(period).is_none() {
+            return Err(ErrorCode::MathOverflow.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Unlocked basis points at `current_time` for a milestone schedule.
+pub fn milestone_bps(milestones: &[Milestone], current_time: u64) -> u64 {
+    milestones
+        .iter()
+        .take_while(|x| x.timestamp <= current_time)
+        .last()
+        .map_or(0, |x| x.cumulative_bps)
+}
+
+/// Total amount unlocked out of `user_amount` at `current_time`, rounded
+/// down until the schedule ends.
+pub fn unlocked_amount(vesting: &Vesting, user_amount: u64, current_time: u64) -> Option<u64> {
+    let unlocked = if vesting.milestone_count > 0 {
+        let milestones = &vesting.milestones[..vesting.milestone_count as usize];
+        mul_div(
+            user_amount,
+            milestone_bps(milestones, current_time),
+            BPS_DENOMINATOR,
+        )?
+    } else {
+        let tge_amount = mul_div(user_amount, vesting.tge_pct, DENOMINATOR)?;
+
+        // Only the TGE amount is released until the cliff ends, the rest
+        // vests linearly over the remaining period.
+        let vesting_start = vesting.start_time.checked_add(vesting.cliff)?;
+        let duration = vesting.period.checked_sub(vesting.cliff)?;
+        let elapsed = current_time.saturating_sub(vesting_start);
+        if elapsed >= duration {
+            return Some(user_amount);
+        }
+
+        let elapsed = elapsed
+            .checked_div(vesting.claim_period)?
+            .checked_mul(vesting.claim_period)?;
+        let vested_amount = mul_div(user_amount.checked_sub(tge_amount)?, elapsed, duration)?;
+        vested_amount.checked_add(tge_amount)?
+    };
+
+    Some(unlocked.min(user_amount))
+}
+
+/// Amount vested to a grant beneficiary at `current_time`, frozen at the
+/// revocation time if the grant was revoked.
+pub fn granted_vested_amount(
+    vesting: &Vesting,
+    user_vesting: &UserVestingAccount,
+    current_time: u64,
+) -> Option<u64> {
+    let time = if user_vesting.revoked_at > 0 {
+        current_time.min(user_vesting.revoked_at)
+    } else {
+        current_time
+    };
+    if time < vesting.start_time {
+        return Some(0);
+    }
+
+    unlocked_amount(vesting, user_vesting.amount, time)
+}
+
+/// Tokens of a sale vesting above `allocated_amount` that were not withdrawn
+/// yet.
+pub fn unallocated_amount(vesting: &Vesting, allocated_amount: u64) -> Option<u64> {
+    Some(
+        vesting
+            .amount
+            .checked_sub(vesting.withdrawn_amount)?
+            .saturating_sub(allocated_amount),
+    )
+}
+
+/// Tokens the vault still owes: the funded amount less what was withdrawn as
+/// unallocated or already claimed. Before unallocated tokens are withdrawn
+/// from a sale vesting they are counted as owed too.
+pub fn outstanding_amount(vesting: &Vesting) -> Option<u64> {
+    vesting
+        .amount
+        .checked_sub(vesting.withdrawn_amount)?
+        .checked_sub(vesting.claimed_amount)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use token_sale::account::{TokenSaleConfig, UserTokenSale};
+    use token_sale::{allocated_sale_amount, record_settlement};
+
+    const START: u64 = 1_000;
+
+    fn linear(tge_pct: u64, period: u64, claim_period: u64, cliff: u64) -> Vesting {
+        Vesting {
+            start_time: START,
+            tge_pct,
+            period,
+            claim_period,
+            cliff,
+            ..Vesting::default()
+        }
+    }
+
+    fn with_milestones(milestones: &[(u64, u64)]) -> Vesting {
+        let mut vesting = Vesting {
+            start_time: START,
+            milestone_count: milestones.len() as u8,
+            ..Vesting::default()
+        };
+        for (i, (timestamp, cumulative_bps)) in milestones.iter().enumerate() {
+            vesting.milestones[i] = Milestone {
+                timestamp: *timestamp,
+                cumulative_bps: *cumulative_bps,
+            };
+        }
+        vesting
+    }
+
+    #[test]
+    fn mul_div_rounds_down() {
+        assert_eq!(mul_div(10, 1, 3), Some(3));
+        assert_eq!(mul_div(2, 1, 3), Some(0));
+        assert_eq!(mul_div(0, u64::MAX, 1), Some(0));
+    }
+
+    #[test]
+    fn mul_div_uses_wide_intermediates() {
+        assert_eq!(mul_div(u64::MAX, u64::MAX, u64::MAX), Some(u64::MAX));
+        assert_eq!(
+            mul_div(u64::MAX, DENOMINATOR - 1, DENOMINATOR),
+            Some(u64::MAX - u64::MAX / DENOMINATOR - 1)
+        );
+    }
+
+    #[test]
+    fn mul_div_rejects_overflow_and_zero_denominator() {
+        assert_eq!(mul_div(u64::MAX, 2, 1), None);
+        assert_eq!(mul_div(1, 1, 0), None);
+    }
+
+    #[test]
+    fn linear_releases_tge_until_cliff_ends() {
+        let vesting = linear(10_000, 100, 10, 40);
+        assert_eq!(unlocked_amount(&vesting, 1_000, START), Some(100));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 40), Some(100));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 50), Some(250));
+    }
+
+    #[test]
+    fn linear_steps_by_claim_period() {
+        let vesting = linear(0, 100, 25, 0);
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 24), Some(0));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 25), Some(250));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 74), Some(500));
+    }
+
+    #[test]
+    fn linear_rounds_down_until_the_end() {
+        let vesting = linear(0, 3, 1, 0);
+        assert_eq!(unlocked_amount(&vesting, 100, START + 1), Some(33));
+        assert_eq!(unlocked_amount(&vesting, 100, START + 2), Some(66));
+        assert_eq!(unlocked_amount(&vesting, 100, START + 3), Some(100));
+    }
+
+    #[test]
+    fn linear_releases_everything_at_period_end() {
+        // The last claim step would end after the period.
+        let vesting = linear(0, 100, 30, 0);
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 99), Some(900));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 100), Some(1_000));
+        assert_eq!(unlocked_amount(&vesting, 1_000, u64::MAX), Some(1_000));
+    }
+
+    #[test]
+    fn linear_handles_max_amount() {
+        let vesting = linear(DENOMINATOR / 10, u64::MAX - START, 1, 0);
+        let tge_amount = u64::MAX / 10;
+        assert_eq!(unlocked_amount(&vesting, u64::MAX, START), Some(tge_amount));
+
+        let half = (u64::MAX - START) / 2;
+        let unlocked = unlocked_amount(&vesting, u64::MAX, START + half).unwrap();
+        let expected = ((u64::MAX - tge_amount) as u128 * half as u128 / (u64::MAX - START) as u128)
+            as u64
+            + tge_amount;
+        assert_eq!(unlocked, expected);
+
+        assert_eq!(
+            unlocked_amount(&vesting, u64::MAX, u64::MAX),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn linear_full_tge_releases_everything() {
+        let vesting = linear(DENOMINATOR, 100, 10, 0);
+        assert_eq!(unlocked_amount(&vesting, u64::MAX, START), Some(u64::MAX));
+    }
+
+    #[test]
+    fn linear_rejects_overflowing_cliff() {
+        let vesting = Vesting {
+            start_time: u64::MAX,
+            ..linear(0, 100, 10, 1)
+        };
+        assert_eq!(unlocked_amount(&vesting, 1_000, u64::MAX), None);
+    }
+
+    #[test]
+    fn milestones_unlock_cumulative_bps() {
+        let vesting = with_milestones(&[(START, 2_500), (START + 10, 10_000)]);
+        assert_eq!(unlocked_amount(&vesting, 1_000, START - 1), Some(0));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START), Some(250));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 9), Some(250));
+        assert_eq!(unlocked_amount(&vesting, 1_000, START + 10), Some(1_000));
+    }
+
+    #[test]
+    fn milestones_handle_max_amount() {
+        let vesting = with_milestones(&[(START, 1), (START + 10, 10_000)]);
+        assert_eq!(
+            unlocked_amount(&vesting, u64::MAX, START),
+            Some(u64::MAX / BPS_DENOMINATOR)
+        );
+        assert_eq!(
+            unlocked_amount(&vesting, u64::MAX, START + 10),
+            Some(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn milestones_validation() {
+        let milestone = |timestamp, cumulative_bps| Milestone {
+            timestamp,
+            cumulative_bps,
+        };
+        assert!(is_valid_milestones(&[milestone(START, 10_000)], START));
+        assert!(!is_valid_milestones(&[], START));
+        assert!(!is_valid_milestones(&[milestone(START - 1, 10_000)], START));
+        assert!(!is_valid_milestones(&[milestone(START, 9_999)], START));
+        assert!(!is_valid_milestones(&[milestone(START, 10_001)], START));
+        assert!(!is_valid_milestones(
+            &[milestone(START, 5_000), milestone(START, 10_000)],
+            START
+        ));
+        assert!(!is_valid_milestones(
+            &[milestone(START, 5_000), milestone(START + 1, 4_000)],
+            START
+        ));
+    }
+
+    #[test]
+    fn schedule_validation() {
+        assert!(validate_schedule(START, 0, 100, 10, 0, &[]).is_ok());
+        assert!(validate_schedule(0, 0, 100, 10, 0, &[]).is_err());
+        assert!(validate_schedule(START, 0, 100, 0, 0, &[]).is_err());
+        assert!(validate_schedule(START, 0, 100, 10, 100, &[]).is_err());
+        assert!(validate_schedule(START, 0, 100, 61, 40, &[]).is_err());
+        assert!(validate_schedule(START, DENOMINATOR + 1, 100, 10, 0, &[]).is_err());
+        assert!(validate_schedule(u64::MAX, 0, 100, 10, 0, &[]).is_err());
+    }
+
+    #[test]
+    fn granted_amount_freezes_at_revocation() {
+        let vesting = linear(0, 100, 1, 0);
+        let mut user_vesting = UserVestingAccount {
+            amount: 1_000,
+            ..UserVestingAccount::default()
+        };
+        assert_eq!(
+            granted_vested_amount(&vesting, &user_vesting, START - 1),
+            Some(0)
+        );
+        assert_eq!(
+            granted_vested_amount(&vesting, &user_vesting, START + 100),
+            Some(1_000)
+        );
+
+        user_vesting.revoked_at = START + 30;
+        assert_eq!(
+            granted_vested_amount(&vesting, &user_vesting, START + 100),
+            Some(300)
+        );
+    }
+
+    #[test]
+    fn outstanding_amount_excludes_withdrawn_and_claimed() {
+        let vesting = Vesting {
+            amount: 1_000,
+            withdrawn_amount: 200,
+            claimed_amount: 300,
+            ..Vesting::default()
+        };
+        assert_eq!(outstanding_amount(&vesting), Some(500));
+
+        let vesting = Vesting {
+            claimed_amount: 1,
+            ..Vesting::default()
+        };
+        assert_eq!(outstanding_amount(&vesting), None);
+    }
+
+    #[test]
+    fn weighted_sale_rounding_is_withdrawn_after_settlement() {
+        let mut config = TokenSaleConfig {
+            weighted: true,
+            price: 2,
+            sale_token_decimals: 0,
+            amount: 10,
+            committed_amount: 15,
+            weighted_commitment: 15,
+            ..TokenSaleConfig::default()
+        };
+        let mut buyers: Vec<UserTokenSale> = (0..3)
+            .map(|_| UserTokenSale {
+                committed_amount: 5,
+                weighted_commitment: 5,
+                ..UserTokenSale::default()
+            })
+            .collect();
+        let mut vesting = Vesting {
+            amount: 100,
+            ..Vesting::default()
+        };
+
+        // Unsettled commitments may still settle to the whole raised amount.
+        let allocated_amount = allocated_sale_amount(&config).unwrap();
+        assert_eq!(allocated_amount, 5);
+        vesting.withdrawn_amount = unallocated_amount(&vesting, allocated_amount).unwrap();
+        assert_eq!(vesting.withdrawn_amount, 95);
+
+        for buyer in buyers.iter_mut() {
+            record_settlement(&mut config, buyer).unwrap();
+            assert_eq!(buyer.purchased_amount, 1);
+        }
+
+        // Each settled purchase rounded down, leaving two tokens unallocated.
+        let allocated_amount = allocated_sale_amount(&config).unwrap();
+        assert_eq!(allocated_amount, 3);
+        assert_eq!(unallocated_amount(&vesting, allocated_amount), Some(2));
+        vesting.withdrawn_amount += 2;
+
+        vesting.claimed_amount = buyers.iter().map(|x| x.purchased_amount).sum();
+        assert_eq!(outstanding_amount(&vesting), Some(0));
+        assert_eq!(unallocated_amount(&vesting, allocated_amount), Some(0));
+    }
+}
@@ -1,19 +1,32 @@
-use anchor_lang::prelude::*;
-
-#[account]
-#[derive(Default)]
-pub struct WhitelistConfig {
-    /// owner address
-    pub owner: Pubkey,
-}
-
-#[account]
-#[derive(Default)]
-pub struct WhitelistAccount {
-    /// whitelist config address
-    pub config: Pubkey,
-    /// Account address
-    pub account: Pubkey,
-    /// Whitelist status
-    pub whitelisted: bool,
-}
+use anchor_lang::prelude::*;
+
+#[account]
+#[derive(Default)]
+pub struct WhitelistConfig {
+    /// owner address
+    pub owner: Pubkey,
+    /// Proposed owner, who must accept to take over the config
+    pub pending_owner: Pubkey,
+}
+
+#[account]
+#[derive(Default)]
+pub struct WhitelistAccount {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Account address
+    pub account: Pubkey,
+    /// Whitelist status
+    pub whitelisted: bool,
+}
+
+#[account]
+#[derive(Default)]
+pub struct WhitelistCollection {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Verified Metaplex collection mint
+    pub collection: Pubkey,
+    /// Whitelist status
+    pub whitelisted: bool,
+}
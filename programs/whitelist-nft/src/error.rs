@@ -0,0 +1,11 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ErrorCode {
+    #[msg("Remaining accounts do not match the batch.")]
+    InvalidBatchAccounts,
+    #[msg("Invalid whitelist account.")]
+    InvalidWhitelistAccount,
+    #[msg("Signer is not the pending owner.")]
+    NotPendingOwner,
+}
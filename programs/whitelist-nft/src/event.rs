@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct WhitelistAdded {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Whitelisted account address
+    pub account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WhitelistRemoved {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Removed account address
+    pub account: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollectionAdded {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Whitelisted collection mint
+    pub collection: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct CollectionRemoved {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Removed collection mint
+    pub collection: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipProposed {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Current owner
+    pub owner: Pubkey,
+    /// Proposed owner
+    pub pending_owner: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct OwnershipTransferred {
+    /// whitelist config address
+    pub config: Pubkey,
+    /// Previous owner
+    pub previous_owner: Pubkey,
+    /// New owner
+    pub owner: Pubkey,
+    pub timestamp: i64,
+}
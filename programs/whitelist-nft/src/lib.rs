@@ -1,8 +1,14 @@
 pub mod account;
 pub mod context;
+pub mod error;
+pub mod event;
 
+use account::WhitelistAccount;
 use anchor_lang::prelude::*;
 use context::*;
+use error::ErrorCode;
+use event::*;
+use program_utils::init_or_load;
 use std::convert::Into;
 
 declare_id!("6HiU5wq8x4gSh8GLyN5oxEtubJw1ArPS5gx72zM76FW2");
@@ -14,13 +20,43 @@ pub mod whitelist_nft {
     pub fn initialize(ctx: Context<Initialize>) -> Result<()> {
         let config = &mut ctx.accounts.config;
         config.owner = ctx.accounts.signer.key();
+        config.pending_owner = Pubkey::default();
 
         Ok(())
     }
 
-    pub fn update_owner(ctx: Context<UpdateOwner>, new_owner: Pubkey) -> Result<()> {
+    pub fn propose_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
         let config = &mut ctx.accounts.config;
-        config.owner = new_owner;
+        config.pending_owner = new_owner;
+
+        emit!(OwnershipProposed {
+            config: config.key(),
+            owner: config.owner,
+            pending_owner: new_owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    // Deprecated alias of `propose_owner` for existing clients. The new owner
+    // still has to call `accept_owner`.
+    pub fn update_owner(ctx: Context<ProposeOwner>, new_owner: Pubkey) -> Result<()> {
+        propose_owner(ctx, new_owner)
+    }
+
+    pub fn accept_owner(ctx: Context<AcceptOwner>) -> Result<()> {
+        let config = &mut ctx.accounts.config;
+        let previous_owner = config.owner;
+        config.owner = config.pending_owner;
+        config.pending_owner = Pubkey::default();
+
+        emit!(OwnershipTransferred {
+            config: config.key(),
+            previous_owner,
+            owner: config.owner,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
 
         Ok(())
     }
@@ -31,6 +67,50 @@ pub mod whitelist_nft {
         whitelist_account.account = account;
         whitelist_account.whitelisted = true;
 
+        emit!(WhitelistAdded {
+            config: whitelist_account.config,
+            account,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn batch_add_whitelist<'info>(
+        ctx: Context<'_, '_, '_, 'info, BatchAddWhitelist<'info>>,
+        mints: Vec<Pubkey>,
+    ) -> Result<()> {
+        if mints.is_empty() || mints.len() != ctx.remaining_accounts.len() {
+            return Err(ErrorCode::InvalidBatchAccounts.into());
+        }
+
+        let config = ctx.accounts.config.key();
+        let timestamp = Clock::get()?.unix_timestamp;
+        for (mint, info) in mints.iter().zip(ctx.remaining_accounts.iter()) {
+            let (address, bump) =
+                Pubkey::find_program_address(&[config.as_ref(), mint.as_ref()], ctx.program_id);
+            if *info.key != address {
+                return Err(ErrorCode::InvalidWhitelistAccount.into());
+            }
+
+            let mut whitelist_account: Account<WhitelistAccount> = init_or_load(
+                info,
+                &[config.as_ref(), mint.as_ref(), &[bump]],
+                &ctx.accounts.signer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            whitelist_account.config = config;
+            whitelist_account.account = *mint;
+            whitelist_account.whitelisted = true;
+            whitelist_account.exit(ctx.program_id)?;
+
+            emit!(WhitelistAdded {
+                config,
+                account: *mint,
+                timestamp,
+            });
+        }
+
         Ok(())
     }
 
@@ -38,6 +118,40 @@ pub mod whitelist_nft {
         let whitelist_account = &mut ctx.accounts.whitelist_account;
         whitelist_account.whitelisted = false;
 
+        emit!(WhitelistRemoved {
+            config: whitelist_account.config,
+            account: whitelist_account.account,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn add_collection(ctx: Context<AddCollection>, collection: Pubkey) -> Result<()> {
+        let whitelist_collection = &mut ctx.accounts.whitelist_collection;
+        whitelist_collection.config = ctx.accounts.config.key();
+        whitelist_collection.collection = collection;
+        whitelist_collection.whitelisted = true;
+
+        emit!(CollectionAdded {
+            config: whitelist_collection.config,
+            collection,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
+        Ok(())
+    }
+
+    pub fn remove_collection(ctx: Context<RemoveCollection>) -> Result<()> {
+        let whitelist_collection = &mut ctx.accounts.whitelist_collection;
+        whitelist_collection.whitelisted = false;
+
+        emit!(CollectionRemoved {
+            config: whitelist_collection.config,
+            collection: whitelist_collection.collection,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+
         Ok(())
     }
 }
@@ -3,7 +3,7 @@ import { Program } from '@project-serum/anchor';
 import { TOKEN_PROGRAM_ID, Token } from '@solana/spl-token';
 import assert from 'assert';
 import { Staking } from '../target/types/staking';
-import { createMint } from './utils';
+import { createMint, wait } from './utils';
 
 describe('staking', () => {
   const provider = anchor.Provider.env();
@@ -57,20 +57,481 @@ describe('staking', () => {
       assert.equal(poolAccount.paused, false);
       assert.equal(poolAccount.stakingMint.toString(), stakingMint.publicKey);
       assert.equal(poolAccount.stakingVault.toString(), stakingVault);
-      assert.equal(poolAccount.rewardMint.toString(), rewardMint.publicKey);
-      assert.equal(poolAccount.rewardVault.toString(), rewardVault);
-      assert.equal(
-        poolAccount.rewardDuration.toString(),
-        rewardDuration.toString(),
-      );
-      assert.equal(poolAccount.rewardDurationEnd.toString(), '0');
+      assert.equal(poolAccount.rewardCount, 1);
+      const reward = poolAccount.rewards[0];
+      assert.equal(reward.rewardMint.toString(), rewardMint.publicKey);
+      assert.equal(reward.rewardVault.toString(), rewardVault);
+      assert.equal(reward.rewardDuration.toString(), rewardDuration.toString());
+      assert.equal(reward.rewardDurationEnd.toString(), '0');
       assert.equal(poolAccount.lockPeriod.toString(), lockPeriod.toString());
-      assert.equal(poolAccount.lastUpdateTime.toString(), '0');
-      assert.equal(poolAccount.rewardRate.toString(), '0');
-      assert.equal(poolAccount.rewardPerTokenStored.toString(), '0');
+      assert.equal(reward.lastUpdateTime.toString(), '0');
+      assert.equal(reward.rewardRate.toString(), '0');
+      assert.equal(reward.rewardPerTokenStored.toString(), '0');
       assert.equal(poolAccount.userStakeCount.toString(), '0');
       assert.equal(poolAccount.funders.length, 5);
       assert.equal(poolAccount.noTier, false);
+      assert.equal(poolAccount.claimBeforeMaturity, false);
+      assert.equal(poolAccount.tierThresholds.length, 7);
+      assert.equal(poolAccount.tierThresholds[0].toString(), '2000000000');
+    });
+  });
+
+  describe('add reward', () => {
+    it('add second reward token', async () => {
+      await initializePool(false);
+
+      const partnerMint = await createMint(provider, 6);
+      const partnerVault = await partnerMint.createAccount(poolSigner);
+
+      await addReward(partnerMint.publicKey, partnerVault);
+
+      const poolAccount = await stakingProgram.account.pool.fetch(
+        pool.publicKey,
+      );
+      assert.equal(poolAccount.rewardCount, 2);
+      const reward = poolAccount.rewards[1];
+      assert.equal(reward.rewardMint.toString(), partnerMint.publicKey);
+      assert.equal(reward.rewardVault.toString(), partnerVault);
+      assert.equal(reward.rewardDuration.toString(), rewardDuration.toString());
+      assert.equal(reward.rewardRate.toString(), '0');
+    });
+
+    it('it fails if reward mint already added', async () => {
+      await initializePool(false);
+
+      const vault = await rewardMint.createAccount(poolSigner);
+
+      try {
+        await addReward(rewardMint.publicKey, vault);
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Reward mint is already distributed by the pool.',
+        );
+      }
+    });
+
+    it('fund and claim second reward token', async () => {
+      await initializePool(false);
+      await createUser();
+
+      const partnerMint = await createMint(provider, 6);
+      const partnerVault = await partnerMint.createAccount(poolSigner);
+      await addReward(partnerMint.publicKey, partnerVault);
+
+      await stake(new anchor.BN(2_000_000_000));
+
+      const funderAccount = await partnerMint.createAccount(wallet.publicKey);
+      await partnerMint.mintTo(funderAccount, wallet.payer, [], 86400 * 7 * 10);
+      await stakingProgram.rpc.fund(1, new anchor.BN(86400 * 7 * 10), {
+        accounts: {
+          pool: pool.publicKey,
+          rewardVault: partnerVault,
+          funder: wallet.publicKey,
+          from: funderAccount,
+          poolSigner,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      const poolAccount = await stakingProgram.account.pool.fetch(
+        pool.publicKey,
+      );
+      assert.equal(poolAccount.rewards[0].rewardRate.toString(), '0');
+      assert.equal(poolAccount.rewards[1].rewardRate.toString(), '10');
+
+      await wait(2);
+
+      const rewardAccount = await partnerMint.createAccount(wallet.publicKey);
+      await stakingProgram.rpc.claim(1, {
+        accounts: {
+          pool: pool.publicKey,
+          stakingVault,
+          rewardVault: partnerVault,
+          user,
+          owner: wallet.publicKey,
+          rewardAccount,
+          poolSigner,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      const rewardTokenAccount = await partnerMint.getAccountInfo(
+        rewardAccount,
+      );
+      assert.ok(rewardTokenAccount.amount.gtn(0));
+      const userAccount = await stakingProgram.account.user.fetch(user);
+      assert.equal(userAccount.rewardPerTokenPending[1].toString(), '0');
+    });
+  });
+
+  describe('early unstake', () => {
+    it('it fails if early unstake is disabled', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7));
+      await createUser();
+      await stake(new anchor.BN(1_000_000_000));
+
+      const treasury = await stakingMint.createAccount(wallet.publicKey);
+      try {
+        await earlyUnstake(new anchor.BN(1_000_000_000), treasury);
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Early unstake is disabled.');
+      }
+    });
+
+    it('unstake before maturity with penalty', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7));
+      await createUser();
+      await stake(new anchor.BN(6_000_000_000));
+
+      const treasury = await stakingMint.createAccount(wallet.publicKey);
+      await stakingProgram.rpc.setEarlyUnstakePenalty(
+        new anchor.BN(1000),
+        false,
+        treasury,
+        {
+          accounts: {
+            pool: pool.publicKey,
+            authority: wallet.publicKey,
+          },
+        },
+      );
+
+      try {
+        await unstake(new anchor.BN(1_000_000_000));
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Need to wait until maturity time to stake or claim.',
+        );
+      }
+
+      await earlyUnstake(new anchor.BN(1_000_000_000), treasury);
+
+      const treasuryAccount = await stakingMint.getAccountInfo(treasury);
+      assert.equal(treasuryAccount.amount.toString(), '100000000');
+      const userAccount = await stakingProgram.account.user.fetch(user);
+      assert.equal(userAccount.balanceStaked.toString(), '5000000000');
+      assert.equal(userAccount.tier.toString(), '1');
+    });
+
+    it('dust early unstake does not delay the reward stream', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7));
+      await createUser();
+
+      const penaltyVault = await stakingMint.createAccount(poolSigner);
+      await addReward(stakingMint.publicKey, penaltyVault);
+
+      await stake(new anchor.BN(6_000_000_000));
+
+      await stakingMint.mintTo(
+        ownerTokenAccount,
+        wallet.payer,
+        [],
+        86400 * 7 * 10,
+      );
+      await stakingProgram.rpc.fund(1, new anchor.BN(86400 * 7 * 10), {
+        accounts: {
+          pool: pool.publicKey,
+          rewardVault: penaltyVault,
+          funder: wallet.publicKey,
+          from: ownerTokenAccount,
+          poolSigner,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+      await stakingProgram.rpc.setEarlyUnstakePenalty(
+        new anchor.BN(1000),
+        false,
+        anchor.web3.PublicKey.default,
+        {
+          accounts: {
+            pool: pool.publicKey,
+            authority: wallet.publicKey,
+          },
+        },
+      );
+
+      const before = (
+        await stakingProgram.account.pool.fetch(pool.publicKey)
+      ).rewards[1];
+
+      await wait(2);
+      await earlyUnstake(new anchor.BN(10), penaltyVault);
+
+      const after = (await stakingProgram.account.pool.fetch(pool.publicKey))
+        .rewards[1];
+      assert.equal(
+        after.rewardDurationEnd.toString(),
+        before.rewardDurationEnd.toString(),
+      );
+      assert.equal(after.rewardRate.toString(), before.rewardRate.toString());
+      const penaltyVaultAccount = await stakingMint.getAccountInfo(
+        penaltyVault,
+      );
+      assert.equal(penaltyVaultAccount.amount.toString(), '6048001');
+    });
+
+    it('it fails without treasury or staking token reward', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7));
+
+      try {
+        await stakingProgram.rpc.setEarlyUnstakePenalty(
+          new anchor.BN(1000),
+          false,
+          anchor.web3.PublicKey.default,
+          {
+            accounts: {
+              pool: pool.publicKey,
+              authority: wallet.publicKey,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Penalties need a treasury or a reward stream in the staking token.',
+        );
+      }
+    });
+  });
+
+  describe('compound', () => {
+    it('compound rewards into a new locked lot', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7), true);
+      await createUser();
+
+      const compoundVault = await stakingMint.createAccount(poolSigner);
+      await addReward(stakingMint.publicKey, compoundVault);
+
+      await stake(new anchor.BN(2_000_000_000));
+
+      await stakingMint.mintTo(
+        ownerTokenAccount,
+        wallet.payer,
+        [],
+        86400 * 7 * 10,
+      );
+      await stakingProgram.rpc.fund(1, new anchor.BN(86400 * 7 * 10), {
+        accounts: {
+          pool: pool.publicKey,
+          rewardVault: compoundVault,
+          funder: wallet.publicKey,
+          from: ownerTokenAccount,
+          poolSigner,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      await wait(2);
+
+      await stakingProgram.rpc.compound(1, {
+        accounts: {
+          pool: pool.publicKey,
+          stakingVault,
+          rewardVault: compoundVault,
+          user,
+          owner: wallet.publicKey,
+          poolSigner,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      const userAccount = await stakingProgram.account.user.fetch(user);
+      assert.ok(userAccount.balanceStaked.gtn(2_000_000_000));
+      assert.equal(userAccount.rewardPerTokenPending[1].toString(), '0');
+      const poolAccount = await stakingProgram.account.pool.fetch(
+        pool.publicKey,
+      );
+      assert.equal(
+        poolAccount.totalStaked.toString(),
+        userAccount.balanceStaked.toString(),
+      );
+    });
+
+    it('it fails if reward mint is not the staking mint', async () => {
+      await initializePool(false);
+      await createUser();
+      await stake(new anchor.BN(1_000_000_000));
+
+      try {
+        await stakingProgram.rpc.compound(0, {
+          accounts: {
+            pool: pool.publicKey,
+            stakingVault,
+            rewardVault,
+            user,
+            owner: wallet.publicKey,
+            poolSigner,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Only rewards in the staking token can be compounded.',
+        );
+      }
+    });
+  });
+
+  describe('claim', () => {
+    it('it fails before maturity', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7));
+      await createUser();
+      await stake(new anchor.BN(1_000_000_000));
+
+      try {
+        await claim();
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Need to wait until maturity time to stake or claim.',
+        );
+      }
+    });
+
+    it('claim once the earliest lot matures', async () => {
+      await initializePool(false, new anchor.BN(2));
+      await createUser();
+      await stake(new anchor.BN(1_000_000_000));
+
+      await wait(3);
+      await stake(new anchor.BN(1_000_000_000));
+
+      await claim();
+    });
+
+    it('claim before maturity if pool allows it', async () => {
+      await initializePool(false, new anchor.BN(86400 * 7), true);
+      await createUser();
+      await stake(new anchor.BN(1_000_000_000));
+
+      await claim();
+
+      await stakingProgram.rpc.setClaimBeforeMaturity(false, {
+        accounts: {
+          pool: pool.publicKey,
+          authority: wallet.publicKey,
+        },
+      });
+
+      try {
+        await claim();
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Need to wait until maturity time to stake or claim.',
+        );
+      }
+    });
+  });
+
+  describe('transfer authority', () => {
+    it('previous authority loses access', async () => {
+      await initializePool(false);
+      let newAuthority = anchor.web3.Keypair.generate();
+
+      await stakingProgram.rpc.proposeAuthority(newAuthority.publicKey, {
+        accounts: {
+          pool: pool.publicKey,
+          authority: wallet.publicKey,
+        },
+      });
+      await stakingProgram.rpc.acceptAuthority({
+        accounts: {
+          pool: pool.publicKey,
+          pendingAuthority: newAuthority.publicKey,
+        },
+        signers: [newAuthority],
+      });
+
+      try {
+        await stakingProgram.rpc.setClaimBeforeMaturity(true, {
+          accounts: {
+            pool: pool.publicKey,
+            authority: wallet.publicKey,
+          },
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'A has one constraint was violated');
+      }
+
+      await stakingProgram.rpc.setClaimBeforeMaturity(true, {
+        accounts: {
+          pool: pool.publicKey,
+          authority: newAuthority.publicKey,
+        },
+        signers: [newAuthority],
+      });
+
+      const poolAccount = await stakingProgram.account.pool.fetch(
+        pool.publicKey,
+      );
+      assert.equal(poolAccount.claimBeforeMaturity, true);
+    });
+  });
+
+  describe('update tier thresholds', () => {
+    const tierThresholds = [
+      new anchor.BN(1_000_000_000),
+      new anchor.BN(2_000_000_000),
+      new anchor.BN(3_000_000_000),
+      new anchor.BN(4_000_000_000),
+      new anchor.BN(5_000_000_000),
+      new anchor.BN(6_000_000_000),
+      new anchor.BN(7_000_000_000),
+    ];
+
+    it('it fails if thresholds are not increasing', async () => {
+      await initializePool(false);
+
+      try {
+        await updateTierThresholds([
+          ...tierThresholds.slice(0, 6),
+          new anchor.BN(6_000_000_000),
+        ]);
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Tier thresholds must be greater than zero and strictly increasing.',
+        );
+      }
+    });
+
+    it('stake uses updated thresholds', async () => {
+      await initializePool(false);
+      await createUser();
+      await stake(new anchor.BN(2_000_000_000));
+
+      let userAccount = await stakingProgram.account.user.fetch(user);
+      assert.equal(userAccount.tier.toString(), '1');
+
+      await updateTierThresholds(tierThresholds);
+
+      const poolAccount = await stakingProgram.account.pool.fetch(
+        pool.publicKey,
+      );
+      for (let i = 0; i < 7; i += 1) {
+        assert.equal(
+          poolAccount.tierThresholds[i].toString(),
+          tierThresholds[i].toString(),
+        );
+      }
+
+      await stake(new anchor.BN(1_000_000_000));
+
+      userAccount = await stakingProgram.account.user.fetch(user);
+      assert.equal(userAccount.tier.toString(), '3');
     });
   });
 
@@ -86,8 +547,8 @@ describe('staking', () => {
       const userAccount = await stakingProgram.account.user.fetch(user);
       assert.equal(userAccount.pool.toString(), pool.publicKey.toString());
       assert.equal(userAccount.owner.toString(), wallet.publicKey.toString());
-      assert.equal(userAccount.rewardPerTokenComplete.toString(), '0');
-      assert.equal(userAccount.rewardPerTokenPending.toString(), '0');
+      assert.equal(userAccount.rewardPerTokenComplete[0].toString(), '0');
+      assert.equal(userAccount.rewardPerTokenPending[0].toString(), '0');
       assert.equal(userAccount.balanceStaked.toString(), amount.toString());
       assert.equal(userAccount.tier.toString(), '1');
     });
@@ -110,6 +571,33 @@ describe('staking', () => {
       assert.equal(userAccount.tier.toString(), '2');
     });
 
+    it('emit staked event', async () => {
+      await initializePool(false);
+      await createUser();
+
+      const amount = new anchor.BN(2_000_000_000);
+
+      let listener: number;
+      const event = new Promise<any>((resolve) => {
+        listener = stakingProgram.addEventListener('Staked', (event) =>
+          resolve(event),
+        );
+      });
+
+      await stake(amount);
+
+      const stakedEvent = await event;
+      await stakingProgram.removeEventListener(listener);
+
+      assert.equal(stakedEvent.pool.toString(), pool.publicKey.toString());
+      assert.equal(stakedEvent.user.toString(), wallet.publicKey.toString());
+      assert.equal(stakedEvent.amount.toString(), amount.toString());
+      assert.equal(stakedEvent.balanceStaked.toString(), amount.toString());
+      assert.equal(stakedEvent.totalStaked.toString(), amount.toString());
+      assert.equal(stakedEvent.previousTier, 0);
+      assert.equal(stakedEvent.tier, 1);
+    });
+
     it('do not update tier if no allocation', async () => {
       await initializePool(true);
       await createUser();
@@ -134,11 +622,44 @@ describe('staking', () => {
       const userAccount = await stakingProgram.account.user.fetch(user);
       assert.equal(userAccount.pool.toString(), pool.publicKey.toString());
       assert.equal(userAccount.owner.toString(), wallet.publicKey.toString());
-      assert.equal(userAccount.rewardPerTokenComplete.toString(), '0');
-      assert.equal(userAccount.rewardPerTokenPending.toString(), '0');
+      assert.equal(userAccount.rewardPerTokenComplete[0].toString(), '0');
+      assert.equal(userAccount.rewardPerTokenPending[0].toString(), '0');
       assert.equal(userAccount.tier.toString(), '0');
     });
 
+    it('top-up does not lock matured stake', async () => {
+      await initializePool(false, new anchor.BN(3));
+      await createUser();
+      await stake(new anchor.BN(1_000_000_000));
+
+      await wait(5);
+
+      await stake(new anchor.BN(2_000_000_000));
+
+      let userAccount = await stakingProgram.account.user.fetch(user);
+      assert.equal(
+        userAccount.stakeLots
+          .filter((x) => !x.amount.isZero())
+          .length.toString(),
+        '2',
+      );
+
+      await unstake(new anchor.BN(1_000_000_000));
+
+      try {
+        await unstake(new anchor.BN(1_000_000_000));
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Need to wait until maturity time to stake or claim.',
+        );
+      }
+
+      userAccount = await stakingProgram.account.user.fetch(user);
+      assert.equal(userAccount.balanceStaked.toString(), '2000000000');
+    });
+
     it('do not update tier if no allocation', async () => {
       await initializePool(true);
       await createUser();
@@ -151,12 +672,17 @@ describe('staking', () => {
     });
   });
 
-  const initializePool = async (noTier: boolean) => {
+  const initializePool = async (
+    noTier: boolean,
+    lock = lockPeriod,
+    claimBeforeMaturity = false,
+  ) => {
     await stakingProgram.rpc.initializePool(
       nonce,
       rewardDuration,
-      lockPeriod,
+      lock,
       noTier,
+      claimBeforeMaturity,
       {
         accounts: {
           authority: wallet.publicKey,
@@ -176,6 +702,64 @@ describe('staking', () => {
     );
   };
 
+  const addReward = async (
+    mint: anchor.web3.PublicKey,
+    vault: anchor.web3.PublicKey,
+  ) => {
+    await stakingProgram.rpc.addReward(rewardDuration, {
+      accounts: {
+        pool: pool.publicKey,
+        authority: wallet.publicKey,
+        rewardMint: mint,
+        rewardVault: vault,
+        poolSigner,
+      },
+    });
+  };
+
+  const claim = async () => {
+    const rewardAccount = await rewardMint.createAccount(wallet.publicKey);
+    await stakingProgram.rpc.claim(0, {
+      accounts: {
+        pool: pool.publicKey,
+        stakingVault,
+        rewardVault,
+        user,
+        owner: wallet.publicKey,
+        rewardAccount,
+        poolSigner,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+  };
+
+  const earlyUnstake = async (
+    amount: anchor.BN,
+    penaltyVault: anchor.web3.PublicKey,
+  ) => {
+    await stakingProgram.rpc.earlyUnstake(amount, {
+      accounts: {
+        pool: pool.publicKey,
+        stakingVault,
+        penaltyVault,
+        user: user,
+        owner: wallet.publicKey,
+        stakeFromAccount: ownerTokenAccount,
+        poolSigner: poolSigner,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      },
+    });
+  };
+
+  const updateTierThresholds = async (tierThresholds: anchor.BN[]) => {
+    await stakingProgram.rpc.updateTierThresholds(tierThresholds, {
+      accounts: {
+        pool: pool.publicKey,
+        authority: wallet.publicKey,
+      },
+    });
+  };
+
   const createUser = async () => {
     ownerTokenAccount = await stakingMint.createAccount(wallet.publicKey);
     await stakingMint.mintTo(
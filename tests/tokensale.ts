@@ -1,7 +1,8 @@
 import * as anchor from '@project-serum/anchor';
 import { Program } from '@project-serum/anchor';
 import assert from 'assert';
-import { Token } from '@solana/spl-token';
+import { Token, TOKEN_PROGRAM_ID } from '@solana/spl-token';
+import { keccak_256 } from 'js-sha3';
 import { TokenSale } from '../target/types/token_sale';
 import { Staking } from '../target/types/staking';
 import { WhitelistNft } from '../target/types/whitelist_nft';
@@ -14,10 +15,12 @@ import {
   createTokenSaleAccountWithNFT,
   whitelistNFT,
   depositWithStaking,
+  registerTier,
   depositWithNft,
   StakingInfo,
   wait,
   withdraw,
+  refund,
 } from './utils';
 
 describe('tokensale', () => {
@@ -40,9 +43,15 @@ describe('tokensale', () => {
   let stakingInfo_2months: StakingInfo;
   let wallet: anchor.Wallet = provider.wallet as anchor.Wallet;
   let saleAmount = new anchor.BN(10000000000);
+  let softCap = new anchor.BN(0);
+  let price = new anchor.BN(2_000_000);
+  let saleTokenDecimals = 6;
   let startTime: anchor.BN;
   let period = new anchor.BN('10');
+  let saleDelay = 5;
+  let withdrawCliff = new anchor.BN(0);
   let stakingAllocation0 = [
+    new anchor.BN(0),
     new anchor.BN(20_000_000),
     new anchor.BN(100_000_000),
     new anchor.BN(200_000_000),
@@ -52,6 +61,7 @@ describe('tokensale', () => {
     new anchor.BN(60_640_000_000),
   ];
   let stakingAllocation1 = [
+    new anchor.BN(0),
     new anchor.BN(60_000_000),
     new anchor.BN(300_000_000),
     new anchor.BN(600_000_000),
@@ -101,20 +111,21 @@ describe('tokensale', () => {
 
     paymentTokenVault = await usdcMint.createAccount(saleSigner);
 
-    startTime = new anchor.BN(Math.floor(Date.now() / 1000));
+    startTime = new anchor.BN(Math.floor(Date.now() / 1000) + saleDelay);
     await tokenSaleProgram.rpc.initializeSale(
       saleNonce,
+      price,
+      saleTokenDecimals,
       saleAmount,
+      softCap,
       startTime,
       period,
-      stakingAllocation0,
-      stakingAllocation1,
+      withdrawCliff,
+      [stakingAllocation0, stakingAllocation1],
       nftAllocation,
       {
         accounts: {
           config: tokenSaleConfig.publicKey,
-          stakingPool0: stakingInfo_7days.pool.publicKey,
-          stakingPool1: stakingInfo_2months.pool.publicKey,
           whitelistConfig: whitelistConfig.publicKey,
           paymentTokenMint: usdcMint.publicKey,
           paymentTokenVault,
@@ -122,6 +133,18 @@ describe('tokensale', () => {
           signer: wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         },
+        remainingAccounts: [
+          {
+            pubkey: stakingInfo_7days.pool.publicKey,
+            isWritable: false,
+            isSigner: false,
+          },
+          {
+            pubkey: stakingInfo_2months.pool.publicKey,
+            isWritable: false,
+            isSigner: false,
+          },
+        ],
         signers: [tokenSaleConfig],
       },
     );
@@ -135,12 +158,13 @@ describe('tokensale', () => {
         );
 
       assert.equal(configAccount.nonce, saleNonce);
+      assert.equal(configAccount.stakingPoolCount, 2);
       assert.equal(
-        configAccount.stakingPool0.toString(),
+        configAccount.stakingPools[0].toString(),
         stakingInfo_7days.pool.publicKey.toString(),
       );
       assert.equal(
-        configAccount.stakingPool1.toString(),
+        configAccount.stakingPools[1].toString(),
         stakingInfo_2months.pool.publicKey.toString(),
       );
       assert.equal(
@@ -155,28 +179,80 @@ describe('tokensale', () => {
         configAccount.paymentTokenVault.toString(),
         paymentTokenVault.toString(),
       );
+      assert.equal(configAccount.paymentTokenDecimals, 6);
+      assert.equal(configAccount.saleTokenDecimals, saleTokenDecimals);
+      assert.equal(configAccount.price.toString(), price.toString());
       assert.equal(configAccount.amount.toString(), saleAmount.toString());
       assert.equal(configAccount.paidAmount.toString(), '0');
+      assert.equal(configAccount.soldAmount.toString(), '0');
       assert.equal(configAccount.withdrawnAmount.toString(), '0');
       assert.equal(configAccount.startTime.toString(), startTime.toString());
       assert.equal(configAccount.period.toString(), period.toString());
+      assert.equal(
+        configAccount.withdrawCliff.toString(),
+        withdrawCliff.toString(),
+      );
       assert.equal(
         configAccount.nftAllocation.toString(),
         nftAllocation.toString(),
       );
-      for (let i = 0; i < 7; i += 1) {
+      for (let i = 0; i < 8; i += 1) {
         assert.equal(
-          configAccount.stakingAllocation0[i].toString(),
+          configAccount.stakingAllocations[0][i].toString(),
           stakingAllocation0[i].toString(),
         );
         assert.equal(
-          configAccount.stakingAllocation1[i].toString(),
+          configAccount.stakingAllocations[1][i].toString(),
           stakingAllocation1[i].toString(),
         );
       }
     });
   });
 
+  describe('Transfer ownership', () => {
+    it('proposing the default address cancels the transfer', async () => {
+      let newOwner = anchor.web3.Keypair.generate();
+
+      await tokenSaleProgram.rpc.proposeOwner(newOwner.publicKey, {
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          signer: wallet.publicKey,
+        },
+      });
+      await tokenSaleProgram.rpc.proposeOwner(
+        anchor.web3.PublicKey.default,
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        },
+      );
+
+      try {
+        await tokenSaleProgram.rpc.acceptOwner({
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: newOwner.publicKey,
+          },
+          signers: [newOwner],
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Signer is not the pending owner.');
+      }
+
+      const configAccount =
+        await tokenSaleProgram.account.tokenSaleConfig.fetch(
+          tokenSaleConfig.publicKey,
+        );
+      assert.equal(
+        configAccount.owner.toString(),
+        wallet.publicKey.toString(),
+      );
+    });
+  });
+
   describe('Create Token Sale Account', () => {
     it('Create Token Sale Account', async () => {
       let [userTokenSale, nonce] =
@@ -203,8 +279,9 @@ describe('tokensale', () => {
         tokenSaleConfig.publicKey.toString(),
       );
       assert.equal(configAccount.paidAmount.toString(), '0');
-      assert.equal(configAccount.usedStaking0Alloc.toString(), '0');
-      assert.equal(configAccount.usedStaking1Alloc.toString(), '0');
+      assert.equal(configAccount.purchasedAmount.toString(), '0');
+      assert.equal(configAccount.usedStakingAlloc[0].toString(), '0');
+      assert.equal(configAccount.usedStakingAlloc[1].toString(), '0');
       assert.equal(configAccount.usedWhitelistAlloc.toString(), '0');
       assert.equal(configAccount.nonce, nonce);
     });
@@ -241,6 +318,16 @@ describe('tokensale', () => {
         fconTokenAccount,
         new anchor.BN(2_000_000_000),
       );
+
+      await registerTier(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        stakingInfo_7days.pool.publicKey,
+        userStakingAccount,
+      );
+      await wait(saleDelay);
     });
 
     it('it fails if amount is 0', async () => {
@@ -299,17 +386,18 @@ describe('tokensale', () => {
       startTime = new anchor.BN(Math.floor(Date.now() / 1000) + 100);
       await tokenSaleProgram.rpc.initializeSale(
         saleNonce,
+        price,
+        saleTokenDecimals,
         saleAmount,
+        softCap,
         startTime,
         period,
-        stakingAllocation0,
-        stakingAllocation1,
+        withdrawCliff,
+        [stakingAllocation0, stakingAllocation1],
         nftAllocation,
         {
           accounts: {
             config: tokenSaleConfig.publicKey,
-            stakingPool0: stakingInfo_7days.pool.publicKey,
-            stakingPool1: stakingInfo_2months.pool.publicKey,
             whitelistConfig: whitelistConfig.publicKey,
             paymentTokenMint: usdcMint.publicKey,
             paymentTokenVault,
@@ -317,6 +405,18 @@ describe('tokensale', () => {
             signer: wallet.publicKey,
             systemProgram: anchor.web3.SystemProgram.programId,
           },
+          remainingAccounts: [
+            {
+              pubkey: stakingInfo_7days.pool.publicKey,
+              isWritable: false,
+              isSigner: false,
+            },
+            {
+              pubkey: stakingInfo_2months.pool.publicKey,
+              isWritable: false,
+              isSigner: false,
+            },
+          ],
           signers: [tokenSaleConfig],
         },
       );
@@ -366,6 +466,99 @@ describe('tokensale', () => {
       }
     });
 
+    it('it fails if tier is not registered', async () => {
+      const stakingAccount = await createStakingUser(
+        provider,
+        stakingProgram,
+        stakingInfo_2months,
+      );
+      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
+      await stake(
+        provider,
+        stakingProgram,
+        stakingInfo_2months,
+        stakingAccount,
+        fconTokenAccount,
+        new anchor.BN(2_000_000_000),
+      );
+
+      try {
+        await depositWithStaking(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          stakingAccount,
+          new anchor.BN(10000),
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Tier is not registered.');
+      }
+    });
+
+    it('it fails to register tier after sale start', async () => {
+      try {
+        await registerTier(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          stakingInfo_7days.pool.publicKey,
+          userStakingAccount,
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Tier registration is closed.');
+      }
+    });
+
+    it('it fails if staking pool is not eligible', async () => {
+      const stakingInfo = await createStakingPool(
+        provider,
+        stakingProgram,
+        fconMint,
+        new anchor.BN(86400 * 7),
+      );
+      const stakingAccount = await createStakingUser(
+        provider,
+        stakingProgram,
+        stakingInfo,
+      );
+      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
+      await stake(
+        provider,
+        stakingProgram,
+        stakingInfo,
+        stakingAccount,
+        fconTokenAccount,
+        new anchor.BN(2_000_000_000),
+      );
+
+      try {
+        await depositWithStaking(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          stakingAccount,
+          new anchor.BN(10000),
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Staking pool is not eligible for the token sale.',
+        );
+      }
+    });
+
     it('it fails no enough allocation', async () => {
       try {
         await depositWithStaking(
@@ -407,8 +600,13 @@ describe('tokensale', () => {
           tokenSaleConfig.publicKey,
         );
       assert.equal(userTokenSaleAccount.paidAmount.toString(), '10000');
-      assert.equal(userTokenSaleAccount.usedStaking0Alloc.toString(), '10000');
+      assert.equal(userTokenSaleAccount.purchasedAmount.toString(), '5000');
+      assert.equal(
+        userTokenSaleAccount.usedStakingAlloc[0].toString(),
+        '10000',
+      );
       assert.equal(tokenSaleConfigAccount.paidAmount.toString(), '10000');
+      assert.equal(tokenSaleConfigAccount.soldAmount.toString(), '5000');
       const paymentTokenVaultAccount = await usdcMint.getAccountInfo(
         paymentTokenVault,
       );
@@ -490,6 +688,8 @@ describe('tokensale', () => {
 
       userVault = await usdcMint.createAccount(wallet.publicKey);
       await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+
+      await wait(saleDelay);
     });
 
     it('it fails if amount is 0', async () => {
@@ -586,48 +786,61 @@ describe('tokensale', () => {
       assert.equal(userTokenSaleAccount.paidAmount.toString(), '10000');
       assert.equal(tokenSaleConfigAccount.paidAmount.toString(), '10000');
       assert.equal(nftIdoInfoAccount.usedAllocation.toString(), '10000');
+      assert.equal(userTokenSaleAccount.usedNftAlloc.toString(), '10000');
       const paymentTokenVaultAccount = await usdcMint.getAccountInfo(
         paymentTokenVault,
       );
       assert.equal(paymentTokenVaultAccount.amount.toString(), '10000');
     });
-  });
-
-  describe('Withdraw', () => {
-    let userTokenSale: anchor.web3.PublicKey;
-    let userVault: anchor.web3.PublicKey;
-    let fconTokenAccount: anchor.web3.PublicKey;
-    let userStakingAccount: anchor.web3.PublicKey;
 
-    beforeEach(async () => {
-      userTokenSale = await createTokenSaleAccount(
-        provider,
-        tokenSaleProgram,
-        tokenSaleConfig.publicKey,
+    it('it fails if wallet cap is exceeded', async () => {
+      await tokenSaleProgram.rpc.setNftAllocation(
+        nftAllocation,
+        new anchor.BN(10000),
+        [],
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        },
       );
 
-      userVault = await usdcMint.createAccount(wallet.publicKey);
-      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
-
-      wait(1);
+      try {
+        await depositWithNft(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          nftAccount,
+          nftIdoInfo,
+          whitelistNftAccount,
+          new anchor.BN(20000),
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'NFT deposit cap per wallet exceeded.');
+      }
+    });
 
-      userStakingAccount = await createStakingUser(
-        provider,
-        stakingProgram,
-        stakingInfo_7days,
-      );
-      fconTokenAccount = await fconMint.createAccount(wallet.publicKey);
-      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
-      await stake(
-        provider,
-        stakingProgram,
-        stakingInfo_7days,
-        userStakingAccount,
-        fconTokenAccount,
-        new anchor.BN(2_000_000_000),
+    it('NFT mint override sets the deposit cap', async () => {
+      const allocation = nftAllocation.add(new anchor.BN(10000));
+      await tokenSaleProgram.rpc.setNftAllocation(
+        nftAllocation,
+        new anchor.BN(0),
+        [{ mint: nftMint.publicKey, allocation }],
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        },
       );
 
-      await depositWithStaking(
+      await depositWithNft(
         provider,
         tokenSaleProgram,
         tokenSaleConfig.publicKey,
@@ -635,28 +848,924 @@ describe('tokensale', () => {
         usdcMint.publicKey,
         paymentTokenVault,
         userVault,
-        userStakingAccount,
-        new anchor.BN(10000),
+        nftAccount,
+        nftIdoInfo,
+        whitelistNftAccount,
+        allocation,
+      );
+
+      const nftIdoInfoAccount = await tokenSaleProgram.account.nftIdoInfo.fetch(
+        nftIdoInfo,
+      );
+      assert.equal(
+        nftIdoInfoAccount.usedAllocation.toString(),
+        allocation.toString(),
       );
+
+      try {
+        await depositWithNft(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          nftAccount,
+          nftIdoInfo,
+          whitelistNftAccount,
+          new anchor.BN(1),
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Insufficient allocation.');
+      }
     });
 
-    it('withdraw', async () => {
-      const recipient = await usdcMint.createAccount(wallet.publicKey);
-      await withdraw(
+    it('it fails if allocation was lowered below used allocation', async () => {
+      await depositWithNft(
         provider,
         tokenSaleProgram,
         tokenSaleConfig.publicKey,
+        userTokenSale,
         usdcMint.publicKey,
         paymentTokenVault,
-        recipient,
-        saleSigner,
+        userVault,
+        nftAccount,
+        nftIdoInfo,
+        whitelistNftAccount,
+        new anchor.BN(20000),
       );
 
-      const tokenSaleConfigAccount =
-        await tokenSaleProgram.account.tokenSaleConfig.fetch(
+      await tokenSaleProgram.rpc.setNftAllocation(
+        new anchor.BN(10000),
+        new anchor.BN(0),
+        [],
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        },
+      );
+
+      try {
+        await depositWithNft(
+          provider,
+          tokenSaleProgram,
           tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          nftAccount,
+          nftIdoInfo,
+          whitelistNftAccount,
+          new anchor.BN(10000),
         );
-      assert.equal(tokenSaleConfigAccount.withdrawnAmount.toString(), '10000');
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Insufficient allocation.');
+      }
+    });
+  });
+
+  describe('Batch whitelist', () => {
+    it('create and update whitelist allocations', async () => {
+      let users = [
+        anchor.web3.Keypair.generate().publicKey,
+        anchor.web3.Keypair.generate().publicKey,
+      ];
+      let whitelistAllocs: anchor.web3.PublicKey[] = [];
+      let userTokenSales: anchor.web3.PublicKey[] = [];
+      for (const user of users) {
+        let [whitelistAlloc, _] =
+          await anchor.web3.PublicKey.findProgramAddress(
+            [
+              Buffer.from('whitelist'),
+              tokenSaleConfig.publicKey.toBuffer(),
+              user.toBuffer(),
+            ],
+            tokenSaleProgram.programId,
+          );
+        whitelistAllocs.push(whitelistAlloc);
+        let [userTokenSale, __] =
+          await anchor.web3.PublicKey.findProgramAddress(
+            [tokenSaleConfig.publicKey.toBuffer(), user.toBuffer()],
+            tokenSaleProgram.programId,
+          );
+        userTokenSales.push(userTokenSale);
+      }
+      const batchWhitelist = async (allocation: anchor.BN) => {
+        await tokenSaleProgram.rpc.batchWhitelist(
+          users.map((user) => ({ user, allocation })),
+          {
+            accounts: {
+              config: tokenSaleConfig.publicKey,
+              signer: wallet.publicKey,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+            remainingAccounts: whitelistAllocs.flatMap((pubkey, i) => [
+              { pubkey, isWritable: true, isSigner: false },
+              { pubkey: userTokenSales[i], isWritable: false, isSigner: false },
+            ]),
+          },
+        );
+      };
+
+      await batchWhitelist(new anchor.BN(10_000_000));
+      await batchWhitelist(new anchor.BN(30_000_000));
+
+      for (let i = 0; i < users.length; i += 1) {
+        const whitelistAllocAccount =
+          await tokenSaleProgram.account.whitelistAlloc.fetch(
+            whitelistAllocs[i],
+          );
+        assert.equal(
+          whitelistAllocAccount.config.toString(),
+          tokenSaleConfig.publicKey.toString(),
+        );
+        assert.equal(
+          whitelistAllocAccount.user.toString(),
+          users[i].toString(),
+        );
+        assert.equal(whitelistAllocAccount.allocation.toString(), '30000000');
+      }
+    });
+
+    it('fails if accounts do not match the batch', async () => {
+      try {
+        await tokenSaleProgram.rpc.batchWhitelist(
+          [
+            {
+              user: anchor.web3.Keypair.generate().publicKey,
+              allocation: new anchor.BN(10_000_000),
+            },
+          ],
+          {
+            accounts: {
+              config: tokenSaleConfig.publicKey,
+              signer: wallet.publicKey,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Remaining accounts do not match the batch.');
+      }
+    });
+  });
+
+  describe('Update whitelist allocation', () => {
+    let userTokenSale: anchor.web3.PublicKey;
+    let userVault: anchor.web3.PublicKey;
+    let whitelistAlloc: anchor.web3.PublicKey;
+
+    const updateWhitelistAllocation = async (allocation: anchor.BN) => {
+      await tokenSaleProgram.rpc.updateWhitelistAllocation(
+        wallet.publicKey,
+        allocation,
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            whitelistAlloc,
+            userTokenSale,
+            signer: wallet.publicKey,
+          },
+        },
+      );
+    };
+
+    beforeEach(async () => {
+      let [_whitelistAlloc, _] = await anchor.web3.PublicKey.findProgramAddress(
+        [
+          Buffer.from('whitelist'),
+          tokenSaleConfig.publicKey.toBuffer(),
+          wallet.publicKey.toBuffer(),
+        ],
+        tokenSaleProgram.programId,
+      );
+      whitelistAlloc = _whitelistAlloc;
+
+      await tokenSaleProgram.rpc.whitelist(
+        wallet.publicKey,
+        new anchor.BN(20_000_000),
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            whitelistAlloc,
+            signer: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+        },
+      );
+
+      userTokenSale = await createTokenSaleAccount(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+      );
+
+      userVault = await usdcMint.createAccount(wallet.publicKey);
+      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+      await wait(saleDelay);
+
+      await tokenSaleProgram.rpc.depositWithWhitelist(
+        new anchor.BN(15_000_000),
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            userTokenSale,
+            paymentTokenMint: usdcMint.publicKey,
+            paymentTokenVault,
+            userVault,
+            whitelistAlloc,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        },
+      );
+    });
+
+    it('update allocation', async () => {
+      await updateWhitelistAllocation(new anchor.BN(15_000_000));
+
+      const whitelistAllocAccount =
+        await tokenSaleProgram.account.whitelistAlloc.fetch(whitelistAlloc);
+      assert.equal(whitelistAllocAccount.allocation.toString(), '15000000');
+    });
+
+    it('cannot lower allocation below used amount', async () => {
+      try {
+        await updateWhitelistAllocation(new anchor.BN(10_000_000));
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Allocation cannot be lower than the used allocation.',
+        );
+      }
+    });
+
+    it('batch cannot lower allocation below used amount', async () => {
+      try {
+        await tokenSaleProgram.rpc.batchWhitelist(
+          [{ user: wallet.publicKey, allocation: new anchor.BN(10_000_000) }],
+          {
+            accounts: {
+              config: tokenSaleConfig.publicKey,
+              signer: wallet.publicKey,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+            remainingAccounts: [
+              { pubkey: whitelistAlloc, isWritable: true, isSigner: false },
+              { pubkey: userTokenSale, isWritable: false, isSigner: false },
+            ],
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Allocation cannot be lower than the used allocation.',
+        );
+      }
+    });
+  });
+
+  describe('Deposit with whitelist proof', () => {
+    let userTokenSale: anchor.web3.PublicKey;
+    let userVault: anchor.web3.PublicKey;
+    let allocation = new anchor.BN(50_000_000);
+    let proof: number[][];
+
+    const leaf = (user: anchor.web3.PublicKey, allocation: anchor.BN) =>
+      Buffer.from(
+        keccak_256.arrayBuffer(
+          Buffer.concat([
+            user.toBuffer(),
+            allocation.toArrayLike(Buffer, 'le', 8),
+          ]),
+        ),
+      );
+    const hashPair = (a: Buffer, b: Buffer) =>
+      Buffer.from(
+        keccak_256.arrayBuffer(
+          Buffer.compare(a, b) <= 0
+            ? Buffer.concat([a, b])
+            : Buffer.concat([b, a]),
+        ),
+      );
+
+    beforeEach(async () => {
+      const a = leaf(wallet.publicKey, allocation);
+      const b = leaf(anchor.web3.Keypair.generate().publicKey, allocation);
+      const c = leaf(anchor.web3.Keypair.generate().publicKey, allocation);
+      const root = hashPair(hashPair(a, b), c);
+      proof = [[...b], [...c]];
+
+      await tokenSaleProgram.rpc.setWhitelistMerkleRoot([...root], {
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          signer: wallet.publicKey,
+        },
+      });
+
+      userTokenSale = await createTokenSaleAccount(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+      );
+
+      userVault = await usdcMint.createAccount(wallet.publicKey);
+      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+      await wait(saleDelay);
+    });
+
+    it('deposit with valid proof', async () => {
+      await tokenSaleProgram.rpc.depositWithWhitelistProof(
+        new anchor.BN(20_000_000),
+        allocation,
+        proof,
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            userTokenSale,
+            paymentTokenMint: usdcMint.publicKey,
+            paymentTokenVault,
+            userVault,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        },
+      );
+
+      const userTokenSaleAccount =
+        await tokenSaleProgram.account.userTokenSale.fetch(userTokenSale);
+      assert.equal(
+        userTokenSaleAccount.usedWhitelistAlloc.toString(),
+        '20000000',
+      );
+      assert.equal(userTokenSaleAccount.paidAmount.toString(), '20000000');
+    });
+
+    it('cannot deposit with a different allocation', async () => {
+      try {
+        await tokenSaleProgram.rpc.depositWithWhitelistProof(
+          new anchor.BN(20_000_000),
+          new anchor.BN(100_000_000),
+          proof,
+          {
+            accounts: {
+              config: tokenSaleConfig.publicKey,
+              userTokenSale,
+              paymentTokenMint: usdcMint.publicKey,
+              paymentTokenVault,
+              userVault,
+              signer: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Invalid whitelist Merkle proof.');
+      }
+    });
+
+    it('cannot deposit more than the allocation', async () => {
+      try {
+        await tokenSaleProgram.rpc.depositWithWhitelistProof(
+          new anchor.BN(60_000_000),
+          allocation,
+          proof,
+          {
+            accounts: {
+              config: tokenSaleConfig.publicKey,
+              userTokenSale,
+              paymentTokenMint: usdcMint.publicKey,
+              paymentTokenVault,
+              userVault,
+              signer: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Insufficient allocation.');
+      }
+    });
+
+    it('cannot set the root after the sale starts', async () => {
+      try {
+        await tokenSaleProgram.rpc.setWhitelistMerkleRoot(
+          new Array(32).fill(0),
+          {
+            accounts: {
+              config: tokenSaleConfig.publicKey,
+              signer: wallet.publicKey,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Token sale already started.');
+      }
+    });
+  });
+
+  describe('Withdraw', () => {
+    let userTokenSale: anchor.web3.PublicKey;
+    let userVault: anchor.web3.PublicKey;
+    let fconTokenAccount: anchor.web3.PublicKey;
+    let userStakingAccount: anchor.web3.PublicKey;
+
+    beforeEach(async () => {
+      userTokenSale = await createTokenSaleAccount(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+      );
+
+      userVault = await usdcMint.createAccount(wallet.publicKey);
+      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+
+      wait(1);
+
+      userStakingAccount = await createStakingUser(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+      );
+      fconTokenAccount = await fconMint.createAccount(wallet.publicKey);
+      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
+      await stake(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+        userStakingAccount,
+        fconTokenAccount,
+        new anchor.BN(2_000_000_000),
+      );
+
+      await registerTier(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        stakingInfo_7days.pool.publicKey,
+        userStakingAccount,
+      );
+      await wait(saleDelay);
+
+      await depositWithStaking(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        usdcMint.publicKey,
+        paymentTokenVault,
+        userVault,
+        userStakingAccount,
+        new anchor.BN(10000),
+      );
+    });
+
+    it('it fails before sale ends', async () => {
+      const recipient = await usdcMint.createAccount(wallet.publicKey);
+      try {
+        await withdraw(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          recipient,
+          saleSigner,
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Cannot withdraw before token sale ends.');
+      }
+    });
+
+    it('withdraw', async () => {
+      await wait(period.toNumber() + 3);
+
+      const recipient = await usdcMint.createAccount(wallet.publicKey);
+      await withdraw(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        usdcMint.publicKey,
+        paymentTokenVault,
+        recipient,
+        saleSigner,
+      );
+
+      const tokenSaleConfigAccount =
+        await tokenSaleProgram.account.tokenSaleConfig.fetch(
+          tokenSaleConfig.publicKey,
+        );
+      assert.equal(tokenSaleConfigAccount.withdrawnAmount.toString(), '10000');
+      assert.equal(tokenSaleConfigAccount.withdrawalCount.toString(), '1');
+      const withdrawal = tokenSaleConfigAccount.withdrawals[0];
+      assert.equal(withdrawal.recipient.toString(), recipient.toString());
+      assert.equal(withdrawal.amount.toString(), '10000');
+      assert.ok(withdrawal.timestamp.gt(startTime.add(period)));
+      const paymentTokenVaultAccount = await usdcMint.getAccountInfo(
+        paymentTokenVault,
+      );
+      assert.equal(paymentTokenVaultAccount.amount.toString(), '0');
+    });
+  });
+
+  describe('Sale phases', () => {
+    let userTokenSale: anchor.web3.PublicKey;
+    let userVault: anchor.web3.PublicKey;
+    let fconTokenAccount: anchor.web3.PublicKey;
+    let userStakingAccount: anchor.web3.PublicKey;
+
+    beforeEach(async () => {
+      // Guaranteed round for whitelist and NFT holders, then an open round
+      // for stakers with doubled allocations.
+      await tokenSaleProgram.rpc.setPhases(
+        [
+          {
+            startTime,
+            endTime: startTime.addn(6),
+            depositPaths: 0b110,
+            allocationMultiplierBps: new anchor.BN(10_000),
+          },
+          {
+            startTime: startTime.addn(7),
+            endTime: startTime.add(period),
+            depositPaths: 0b111,
+            allocationMultiplierBps: new anchor.BN(20_000),
+          },
+        ],
+        {
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        },
+      );
+
+      userTokenSale = await createTokenSaleAccount(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+      );
+
+      userVault = await usdcMint.createAccount(wallet.publicKey);
+      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+
+      userStakingAccount = await createStakingUser(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+      );
+      fconTokenAccount = await fconMint.createAccount(wallet.publicKey);
+      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
+      await stake(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+        userStakingAccount,
+        fconTokenAccount,
+        new anchor.BN(2_000_000_000),
+      );
+
+      await registerTier(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        stakingInfo_7days.pool.publicKey,
+        userStakingAccount,
+      );
+      await wait(saleDelay);
+    });
+
+    it('deposit paths and multiplier follow the active phase', async () => {
+      try {
+        await depositWithStaking(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          userStakingAccount,
+          new anchor.BN(10000),
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Deposit path is not allowed in the current phase.',
+        );
+      }
+
+      await wait(4);
+
+      // Tier 1 allocation is 20_000_000 and doubled in the open round.
+      await depositWithStaking(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        usdcMint.publicKey,
+        paymentTokenVault,
+        userVault,
+        userStakingAccount,
+        new anchor.BN(30_000_000),
+      );
+
+      const userTokenSaleAccount =
+        await tokenSaleProgram.account.userTokenSale.fetch(userTokenSale);
+      assert.equal(
+        userTokenSaleAccount.usedStakingAlloc[0].toString(),
+        '30000000',
+      );
+    });
+  });
+
+  describe('Weighted sale', () => {
+    let userTokenSale: anchor.web3.PublicKey;
+    let userVault: anchor.web3.PublicKey;
+    let fconTokenAccount: anchor.web3.PublicKey;
+    let userStakingAccount: anchor.web3.PublicKey;
+    const tierWeights = [0, 1, 2, 3, 4, 5, 6, 7].map((x) => new anchor.BN(x));
+
+    beforeEach(async () => {
+      await tokenSaleProgram.rpc.setWeightedMode(true, tierWeights, {
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          signer: wallet.publicKey,
+        },
+      });
+
+      userTokenSale = await createTokenSaleAccount(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+      );
+
+      userVault = await usdcMint.createAccount(wallet.publicKey);
+      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+
+      userStakingAccount = await createStakingUser(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+      );
+      fconTokenAccount = await fconMint.createAccount(wallet.publicKey);
+      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
+      await stake(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+        userStakingAccount,
+        fconTokenAccount,
+        new anchor.BN(2_000_000_000),
+      );
+
+      await registerTier(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        stakingInfo_7days.pool.publicKey,
+        userStakingAccount,
+      );
+      await wait(saleDelay);
+    });
+
+    const commit = async (amount: anchor.BN) => {
+      await tokenSaleProgram.rpc.commit(amount, {
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          userTokenSale,
+          paymentTokenMint: usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          stakingAccount: userStakingAccount,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+    };
+
+    const settle = async () => {
+      await tokenSaleProgram.rpc.settle({
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          userTokenSale,
+          paymentTokenMint: usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          saleSigner,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+    };
+
+    it('it fails to deposit in weighted mode', async () => {
+      try {
+        await depositWithStaking(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          userStakingAccount,
+          new anchor.BN(10000),
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Token sale requires commitments.');
+      }
+    });
+
+    it('it fails to settle before sale ends', async () => {
+      await commit(new anchor.BN(10000));
+
+      try {
+        await settle();
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Token sale not ended.');
+      }
+    });
+
+    it('commit and settle', async () => {
+      await commit(new anchor.BN(10000));
+
+      let userTokenSaleAccount =
+        await tokenSaleProgram.account.userTokenSale.fetch(userTokenSale);
+      assert.equal(userTokenSaleAccount.committedAmount.toString(), '10000');
+      assert.equal(userTokenSaleAccount.weightedCommitment.toString(), '10000');
+      assert.equal(userTokenSaleAccount.paidAmount.toString(), '0');
+
+      await wait(period.toNumber() + 3);
+      await settle();
+
+      userTokenSaleAccount = await tokenSaleProgram.account.userTokenSale.fetch(
+        userTokenSale,
+      );
+      assert.ok(userTokenSaleAccount.settled);
+      assert.equal(userTokenSaleAccount.paidAmount.toString(), '10000');
+      assert.equal(userTokenSaleAccount.purchasedAmount.toString(), '5000');
+      const tokenSaleConfigAccount =
+        await tokenSaleProgram.account.tokenSaleConfig.fetch(
+          tokenSaleConfig.publicKey,
+        );
+      assert.equal(tokenSaleConfigAccount.paidAmount.toString(), '10000');
+      assert.equal(tokenSaleConfigAccount.committedAmount.toString(), '10000');
+    });
+  });
+
+  describe('Refund', () => {
+    let userTokenSale: anchor.web3.PublicKey;
+    let userVault: anchor.web3.PublicKey;
+    let fconTokenAccount: anchor.web3.PublicKey;
+    let userStakingAccount: anchor.web3.PublicKey;
+
+    beforeEach(async () => {
+      userTokenSale = await createTokenSaleAccount(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+      );
+
+      userVault = await usdcMint.createAccount(wallet.publicKey);
+      await usdcMint.mintTo(userVault, wallet.payer, [], 10000000000000);
+
+      userStakingAccount = await createStakingUser(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+      );
+      fconTokenAccount = await fconMint.createAccount(wallet.publicKey);
+      await fconMint.mintTo(fconTokenAccount, wallet.payer, [], 2_000_000_000);
+      await stake(
+        provider,
+        stakingProgram,
+        stakingInfo_7days,
+        userStakingAccount,
+        fconTokenAccount,
+        new anchor.BN(2_000_000_000),
+      );
+
+      await registerTier(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        stakingInfo_7days.pool.publicKey,
+        userStakingAccount,
+      );
+      await wait(saleDelay);
+
+      await depositWithStaking(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        usdcMint.publicKey,
+        paymentTokenVault,
+        userVault,
+        userStakingAccount,
+        new anchor.BN(10000),
+      );
+    });
+
+    it('it fails if sale is not cancelled', async () => {
+      try {
+        await refund(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          userTokenSale,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          userVault,
+          saleSigner,
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Refund is not available.');
+      }
+    });
+
+    it('it fails to cancel after sale ends', async () => {
+      await wait(period.toNumber() + 3);
+
+      try {
+        await tokenSaleProgram.rpc.cancelSale({
+          accounts: {
+            config: tokenSaleConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Cannot cancel token sale after it ends.');
+      }
+    });
+
+    it('refund after cancel', async () => {
+      await tokenSaleProgram.rpc.cancelSale({
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          signer: wallet.publicKey,
+        },
+      });
+
+      const recipient = await usdcMint.createAccount(wallet.publicKey);
+      try {
+        await withdraw(
+          provider,
+          tokenSaleProgram,
+          tokenSaleConfig.publicKey,
+          usdcMint.publicKey,
+          paymentTokenVault,
+          recipient,
+          saleSigner,
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Token sale cancelled.');
+      }
+
+      await refund(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        usdcMint.publicKey,
+        paymentTokenVault,
+        userVault,
+        saleSigner,
+      );
+
+      const userTokenSaleAccount =
+        await tokenSaleProgram.account.userTokenSale.fetch(userTokenSale);
+      assert.equal(userTokenSaleAccount.paidAmount.toString(), '0');
+      assert.equal(userTokenSaleAccount.purchasedAmount.toString(), '0');
+      const tokenSaleConfigAccount =
+        await tokenSaleProgram.account.tokenSaleConfig.fetch(
+          tokenSaleConfig.publicKey,
+        );
+      assert.equal(tokenSaleConfigAccount.refundedAmount.toString(), '10000');
       const paymentTokenVaultAccount = await usdcMint.getAccountInfo(
         paymentTokenVault,
       );
@@ -51,6 +51,7 @@ export const createStakingPool = async (
     new anchor.BN(86400 * 30),
     lockPeriod,
     false,
+    false,
     {
       accounts: {
         authority: provider.wallet.publicKey,
@@ -214,6 +215,25 @@ export const whitelistNFT = async (
   return whitelistAccount;
 };
 
+export const registerTier = async (
+  provider: anchor.Provider,
+  tokenSaleProgram: Program<TokenSale>,
+  tokenSaleConfig: anchor.web3.PublicKey,
+  userTokenSale: anchor.web3.PublicKey,
+  stakingPool: anchor.web3.PublicKey,
+  stakingAccount: anchor.web3.PublicKey,
+) => {
+  await tokenSaleProgram.rpc.registerTier({
+    accounts: {
+      config: tokenSaleConfig,
+      userTokenSale,
+      stakingPool,
+      stakingAccount,
+      signer: provider.wallet.publicKey,
+    },
+  });
+};
+
 export const depositWithStaking = async (
   provider: anchor.Provider,
   tokenSaleProgram: Program<TokenSale>,
@@ -290,6 +310,30 @@ export const withdraw = async (
   });
 };
 
+export const refund = async (
+  provider: anchor.Provider,
+  tokenSaleProgram: Program<TokenSale>,
+  tokenSaleConfig: anchor.web3.PublicKey,
+  userTokenSale: anchor.web3.PublicKey,
+  paymentTokenMint: anchor.web3.PublicKey,
+  paymentTokenVault: anchor.web3.PublicKey,
+  userVault: anchor.web3.PublicKey,
+  saleSigner: anchor.web3.PublicKey,
+) => {
+  await tokenSaleProgram.rpc.refund({
+    accounts: {
+      config: tokenSaleConfig,
+      userTokenSale,
+      paymentTokenMint,
+      paymentTokenVault,
+      userVault,
+      saleSigner,
+      signer: provider.wallet.publicKey,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    },
+  });
+};
+
 export const createUserVestingACcount = async (
   provider: anchor.Provider,
   vestingProgram: Program<Vesting>,
@@ -15,6 +15,7 @@ import {
   stake,
   createStakingUser,
   depositWithStaking,
+  registerTier,
   createUserVestingACcount,
 } from './utils';
 
@@ -38,9 +39,15 @@ describe('vesting', () => {
   let stakingInfo_7days: StakingInfo;
   let stakingInfo_2months: StakingInfo;
   let saleAmount = new anchor.BN(10000000000);
+  let softCap = new anchor.BN(0);
+  let price = new anchor.BN(1_000_000);
+  let saleTokenDecimals = 6;
   let startTime: anchor.BN;
   let period = new anchor.BN('10');
+  let saleDelay = 5;
+  let withdrawCliff = new anchor.BN(0);
   let stakingAllocation0 = [
+    new anchor.BN(0),
     new anchor.BN(20_000_000),
     new anchor.BN(100_000_000),
     new anchor.BN(200_000_000),
@@ -50,6 +57,7 @@ describe('vesting', () => {
     new anchor.BN(60_640_000_000),
   ];
   let stakingAllocation1 = [
+    new anchor.BN(0),
     new anchor.BN(60_000_000),
     new anchor.BN(300_000_000),
     new anchor.BN(600_000_000),
@@ -60,7 +68,7 @@ describe('vesting', () => {
   ];
   let nftAllocation = new anchor.BN(20_000_000);
   let tokenMint: Token;
-  let vesting: anchor.web3.Keypair;
+  let vesting: anchor.web3.PublicKey;
   let vestingSigner: anchor.web3.PublicKey;
   let vestingNonce: number;
   let tokenVault: anchor.web3.PublicKey;
@@ -70,37 +78,13 @@ describe('vesting', () => {
   let vestingAmount = new anchor.BN(10000000000);
   let vestingPeriod = new anchor.BN(86400);
   let vestingClaimPeriod = new anchor.BN(20);
+  let vestingCliff = new anchor.BN(0);
+  // [seconds after vesting start, cumulative basis points]
+  let milestoneOffsets: number[][] = [];
   let vestingStartTime: anchor.BN;
   let wallet: anchor.Wallet = provider.wallet as anchor.Wallet;
 
-  beforeEach(async () => {
-    whitelistConfig = anchor.web3.Keypair.generate();
-
-    await whitelistProgram.rpc.initialize({
-      accounts: {
-        config: whitelistConfig.publicKey,
-        signer: wallet.publicKey,
-        systemProgram: anchor.web3.SystemProgram.programId,
-      },
-      signers: [whitelistConfig],
-    });
-
-    fconMint = await createMint(provider, 4);
-    stakingInfo_7days = await createStakingPool(
-      provider,
-      stakingProgram,
-      fconMint,
-      new anchor.BN(86400 * 7),
-    );
-    stakingInfo_2months = await createStakingPool(
-      provider,
-      stakingProgram,
-      fconMint,
-      new anchor.BN(86400 * 60),
-    );
-
-    usdcMint = await createMint(provider, 6);
-
+  const initializeSale = async () => {
     tokenSaleConfig = anchor.web3.Keypair.generate();
 
     let [_saleSigner, nonce] = await anchor.web3.PublicKey.findProgramAddress(
@@ -112,20 +96,21 @@ describe('vesting', () => {
 
     paymentTokenVault = await usdcMint.createAccount(saleSigner);
 
-    startTime = new anchor.BN(Math.floor(Date.now() / 1000));
+    startTime = new anchor.BN(Math.floor(Date.now() / 1000) + saleDelay);
     await tokenSaleProgram.rpc.initializeSale(
       saleNonce,
+      price,
+      saleTokenDecimals,
       saleAmount,
+      softCap,
       startTime,
       period,
-      stakingAllocation0,
-      stakingAllocation1,
+      withdrawCliff,
+      [stakingAllocation0, stakingAllocation1],
       nftAllocation,
       {
         accounts: {
           config: tokenSaleConfig.publicKey,
-          stakingPool0: stakingInfo_7days.pool.publicKey,
-          stakingPool1: stakingInfo_2months.pool.publicKey,
           whitelistConfig: whitelistConfig.publicKey,
           paymentTokenMint: usdcMint.publicKey,
           paymentTokenVault,
@@ -133,16 +118,63 @@ describe('vesting', () => {
           signer: wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
         },
+        remainingAccounts: [
+          {
+            pubkey: stakingInfo_7days.pool.publicKey,
+            isWritable: false,
+            isSigner: false,
+          },
+          {
+            pubkey: stakingInfo_2months.pool.publicKey,
+            isWritable: false,
+            isSigner: false,
+          },
+        ],
         signers: [tokenSaleConfig],
       },
     );
+  };
+
+  beforeEach(async () => {
+    whitelistConfig = anchor.web3.Keypair.generate();
+
+    await whitelistProgram.rpc.initialize({
+      accounts: {
+        config: whitelistConfig.publicKey,
+        signer: wallet.publicKey,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      },
+      signers: [whitelistConfig],
+    });
+
+    fconMint = await createMint(provider, 4);
+    stakingInfo_7days = await createStakingPool(
+      provider,
+      stakingProgram,
+      fconMint,
+      new anchor.BN(86400 * 7),
+    );
+    stakingInfo_2months = await createStakingPool(
+      provider,
+      stakingProgram,
+      fconMint,
+      new anchor.BN(86400 * 60),
+    );
+
+    usdcMint = await createMint(provider, 6);
+
+    await initializeSale();
 
     tokenMint = await createMint(provider, 6);
 
-    vesting = anchor.web3.Keypair.generate();
+    let [_vesting, _] = await anchor.web3.PublicKey.findProgramAddress(
+      [Buffer.from('vesting'), tokenSaleConfig.publicKey.toBuffer()],
+      vestingProgram.programId,
+    );
+    vesting = _vesting;
     let [_vestingSigner, _vestingNonce] =
       await anchor.web3.PublicKey.findProgramAddress(
-        [vesting.publicKey.toBuffer()],
+        [vesting.toBuffer()],
         vestingProgram.programId,
       );
     vestingSigner = _vestingSigner;
@@ -152,7 +184,9 @@ describe('vesting', () => {
     fundVault = await tokenMint.createAccount(wallet.publicKey);
     await tokenMint.mintTo(fundVault, wallet.payer, [], 100000000000000);
 
-    vestingStartTime = new anchor.BN(Math.floor(Date.now() / 1000));
+    vestingStartTime = new anchor.BN(
+      Math.floor(Date.now() / 1000) + saleDelay,
+    );
 
     await vestingProgram.rpc.initializeVesting(
       vestingAmount,
@@ -160,10 +194,15 @@ describe('vesting', () => {
       vestingStartTime,
       vestingPeriod,
       vestingClaimPeriod,
+      vestingCliff,
+      milestoneOffsets.map(([offset, cumulativeBps]) => ({
+        timestamp: vestingStartTime.addn(offset),
+        cumulativeBps: new anchor.BN(cumulativeBps),
+      })),
       {
         accounts: {
           idoConfig: tokenSaleConfig.publicKey,
-          vesting: vesting.publicKey,
+          vesting,
           tokenMint: tokenMint.publicKey,
           tokenVault,
           fundVault,
@@ -172,7 +211,6 @@ describe('vesting', () => {
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: anchor.web3.SystemProgram.programId,
         },
-        signers: [vesting],
       },
     );
   });
@@ -180,7 +218,7 @@ describe('vesting', () => {
   describe('initialize_vesting', () => {
     it('Check initialized vesting', async () => {
       const vestingAccount = await vestingProgram.account.vesting.fetch(
-        vesting.publicKey,
+        vesting,
       );
       assert.equal(vestingAccount.nonce, vestingNonce);
       assert.equal(
@@ -197,6 +235,8 @@ describe('vesting', () => {
         vestingStartTime.toString(),
       );
       assert.equal(vestingAccount.period.toString(), vestingPeriod.toString());
+      assert.equal(vestingAccount.cliff.toString(), vestingCliff.toString());
+      assert.equal(vestingAccount.milestoneCount, 0);
       assert.equal(
         vestingAccount.claimPeriod.toString(),
         vestingClaimPeriod.toString(),
@@ -211,19 +251,155 @@ describe('vesting', () => {
         vestingAmount.toString(),
       );
     });
+
+    it('it fails to initialize a second vesting for the sale', async () => {
+      try {
+        await vestingProgram.rpc.initializeVesting(
+          vestingAmount,
+          tgePercentage,
+          vestingStartTime,
+          vestingPeriod,
+          vestingClaimPeriod,
+          vestingCliff,
+          [],
+          {
+            accounts: {
+              idoConfig: tokenSaleConfig.publicKey,
+              vesting,
+              tokenMint: tokenMint.publicKey,
+              tokenVault,
+              fundVault,
+              vestingSigner,
+              signer: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.ok(
+          err.logs.some((log: string) => log.includes('already in use')),
+        );
+      }
+    });
+  });
+
+    it('it fails if cliff is not shorter than period', async () => {
+      // The current sale already has a vesting.
+      await initializeSale();
+      const [invalidVesting, _] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [Buffer.from('vesting'), tokenSaleConfig.publicKey.toBuffer()],
+          vestingProgram.programId,
+        );
+      const [invalidVestingSigner, __] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [invalidVesting.toBuffer()],
+          vestingProgram.programId,
+        );
+      const invalidTokenVault = await tokenMint.createAccount(
+        invalidVestingSigner,
+      );
+
+      try {
+        await vestingProgram.rpc.initializeVesting(
+          vestingAmount,
+          tgePercentage,
+          vestingStartTime,
+          vestingPeriod,
+          vestingClaimPeriod,
+          vestingPeriod,
+          [],
+          {
+            accounts: {
+              idoConfig: tokenSaleConfig.publicKey,
+              vesting: invalidVesting,
+              tokenMint: tokenMint.publicKey,
+              tokenVault: invalidTokenVault,
+              fundVault,
+              vestingSigner: invalidVestingSigner,
+              signer: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Cliff must be shorter than period.');
+      }
+    });
+  });
+
+  describe('initialize_vesting with milestones', () => {
+    it('it fails if milestones do not unlock everything', async () => {
+      // The current sale already has a vesting.
+      await initializeSale();
+      const [invalidVesting, _] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [Buffer.from('vesting'), tokenSaleConfig.publicKey.toBuffer()],
+          vestingProgram.programId,
+        );
+      const [invalidVestingSigner, __] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [invalidVesting.toBuffer()],
+          vestingProgram.programId,
+        );
+      const invalidTokenVault = await tokenMint.createAccount(
+        invalidVestingSigner,
+      );
+
+      try {
+        await vestingProgram.rpc.initializeVesting(
+          vestingAmount,
+          new anchor.BN(0),
+          vestingStartTime,
+          new anchor.BN(0),
+          new anchor.BN(0),
+          new anchor.BN(0),
+          [
+            { timestamp: vestingStartTime, cumulativeBps: new anchor.BN(1000) },
+            {
+              timestamp: vestingStartTime.addn(60),
+              cumulativeBps: new anchor.BN(9000),
+            },
+          ],
+          {
+            accounts: {
+              idoConfig: tokenSaleConfig.publicKey,
+              vesting: invalidVesting,
+              tokenMint: tokenMint.publicKey,
+              tokenVault: invalidTokenVault,
+              fundVault,
+              vestingSigner: invalidVestingSigner,
+              signer: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+          },
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.msg,
+          'Milestones must be ordered and unlock 100% at the last one.',
+        );
+      }
+    });
   });
 
   describe('create_vesting_account', () => {
     it('create vesting account', async () => {
       let [userVestingAccount, userVestingNonce] =
         await anchor.web3.PublicKey.findProgramAddress(
-          [vesting.publicKey.toBuffer(), wallet.publicKey.toBuffer()],
+          [vesting.toBuffer(), wallet.publicKey.toBuffer()],
           vestingProgram.programId,
         );
 
       await vestingProgram.rpc.createVestingAccount({
         accounts: {
-          vesting: vesting.publicKey,
+          vesting,
           userVesting: userVestingAccount,
           signer: wallet.publicKey,
           systemProgram: anchor.web3.SystemProgram.programId,
@@ -236,7 +412,7 @@ describe('vesting', () => {
         );
       assert.equal(
         vestingAccount.vesting.toString(),
-        vesting.publicKey.toString(),
+        vesting.toString(),
       );
       assert.equal(
         vestingAccount.nonce.toString(),
@@ -281,6 +457,16 @@ describe('vesting', () => {
         new anchor.BN(2_000_000_000),
       );
 
+      await registerTier(
+        provider,
+        tokenSaleProgram,
+        tokenSaleConfig.publicKey,
+        userTokenSale,
+        stakingInfo_7days.pool.publicKey,
+        userStakingAccount,
+      );
+      await wait(saleDelay);
+
       await depositWithStaking(
         provider,
         tokenSaleProgram,
@@ -296,7 +482,7 @@ describe('vesting', () => {
       userVestingAccount = await createUserVestingACcount(
         provider,
         vestingProgram,
-        vesting.publicKey,
+        vesting,
       );
 
       claimRecipient = await tokenMint.createAccount(wallet.publicKey);
@@ -308,7 +494,7 @@ describe('vesting', () => {
       await vestingProgram.rpc.claim({
         accounts: {
           idoConfig: tokenSaleConfig.publicKey,
-          vesting: vesting.publicKey,
+          vesting,
           userVesting: userVestingAccount,
           userTokenSale,
           tokenMint: tokenMint.publicKey,
@@ -320,9 +506,9 @@ describe('vesting', () => {
         },
       });
 
-      const tgeAmount = vestingAmount
-        .mul(userAmount)
-        .div(saleAmount)
+      const tgeAmount = userAmount
+        .mul(new anchor.BN(10).pow(new anchor.BN(saleTokenDecimals)))
+        .div(price)
         .mul(tgePercentage)
         .div(new anchor.BN('100000'));
       const recipientAccount = await tokenMint.getAccountInfo(claimRecipient);
@@ -335,13 +521,65 @@ describe('vesting', () => {
       assert.equal(vestingAccount.claimed.toString(), tgeAmount.toString());
     });
 
+    it('it fails to claim before sale ends', async () => {
+      try {
+        await vestingProgram.rpc.claim({
+          accounts: {
+            idoConfig: tokenSaleConfig.publicKey,
+            vesting,
+            userVesting: userVestingAccount,
+            userTokenSale,
+            tokenMint: tokenMint.publicKey,
+            tokenVault,
+            claimRecipient,
+            vestingSigner,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Cannot claim before token sale ends.');
+      }
+    });
+
+    it('it fails to claim from a cancelled sale', async () => {
+      await tokenSaleProgram.rpc.cancelSale({
+        accounts: {
+          config: tokenSaleConfig.publicKey,
+          signer: wallet.publicKey,
+        },
+      });
+      await wait(10);
+
+      try {
+        await vestingProgram.rpc.claim({
+          accounts: {
+            idoConfig: tokenSaleConfig.publicKey,
+            vesting,
+            userVesting: userVestingAccount,
+            userTokenSale,
+            tokenMint: tokenMint.publicKey,
+            tokenVault,
+            claimRecipient,
+            vestingSigner,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Token sale cancelled.');
+      }
+    });
+
     it('claim available amounts', async () => {
       await wait(35);
 
       await vestingProgram.rpc.claim({
         accounts: {
           idoConfig: tokenSaleConfig.publicKey,
-          vesting: vesting.publicKey,
+          vesting,
           userVesting: userVestingAccount,
           userTokenSale,
           tokenMint: tokenMint.publicKey,
@@ -353,7 +591,9 @@ describe('vesting', () => {
         },
       });
 
-      const userVestingAmount = vestingAmount.mul(userAmount).div(saleAmount);
+      const userVestingAmount = userAmount
+        .mul(new anchor.BN(10).pow(new anchor.BN(saleTokenDecimals)))
+        .div(price);
 
       const tgeAmount = userVestingAmount
         .mul(tgePercentage)
@@ -378,5 +618,370 @@ describe('vesting', () => {
         tgeAmount.add(batchAmount).toString(),
       );
     });
+
+    describe('with milestones', () => {
+      before(() => {
+        milestoneOffsets = [
+          [0, 1000],
+          [30, 2500],
+          [3600, 10000],
+        ];
+      });
+
+      after(() => {
+        milestoneOffsets = [];
+      });
+
+      it('claim unlocked milestones', async () => {
+        await wait(35);
+
+        await vestingProgram.rpc.claim({
+          accounts: {
+            idoConfig: tokenSaleConfig.publicKey,
+            vesting,
+            userVesting: userVestingAccount,
+            userTokenSale,
+            tokenMint: tokenMint.publicKey,
+            tokenVault,
+            claimRecipient,
+            vestingSigner,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+
+        const unlockedAmount = userAmount
+          .mul(new anchor.BN(10).pow(new anchor.BN(saleTokenDecimals)))
+          .div(price)
+          .mul(new anchor.BN(2500))
+          .div(new anchor.BN(10000));
+        const recipientAccount = await tokenMint.getAccountInfo(claimRecipient);
+        assert.equal(
+          recipientAccount.amount.toString(),
+          unlockedAmount.toString(),
+        );
+      });
+    });
+
+    describe('with cliff', () => {
+      before(() => {
+        vestingCliff = new anchor.BN(60);
+      });
+
+      after(() => {
+        vestingCliff = new anchor.BN(0);
+      });
+
+      it('claim only tgeAmount during cliff', async () => {
+        await wait(35);
+
+        await vestingProgram.rpc.claim({
+          accounts: {
+            idoConfig: tokenSaleConfig.publicKey,
+            vesting,
+            userVesting: userVestingAccount,
+            userTokenSale,
+            tokenMint: tokenMint.publicKey,
+            tokenVault,
+            claimRecipient,
+            vestingSigner,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+
+        const tgeAmount = userAmount
+          .mul(new anchor.BN(10).pow(new anchor.BN(saleTokenDecimals)))
+          .div(price)
+          .mul(tgePercentage)
+          .div(new anchor.BN('100000'));
+        const recipientAccount = await tokenMint.getAccountInfo(claimRecipient);
+        assert.equal(recipientAccount.amount.toString(), tgeAmount.toString());
+      });
+    });
+  });
+
+  describe('grant', () => {
+    let grantVesting: anchor.web3.Keypair;
+    let grantVestingSigner: anchor.web3.PublicKey;
+    let grantTokenVault: anchor.web3.PublicKey;
+    let grantAmount = new anchor.BN(1_000_000);
+
+    const findUserVesting = async (beneficiary: anchor.web3.PublicKey) => {
+      const [userVesting, _] = await anchor.web3.PublicKey.findProgramAddress(
+        [grantVesting.publicKey.toBuffer(), beneficiary.toBuffer()],
+        vestingProgram.programId,
+      );
+      return userVesting;
+    };
+
+    beforeEach(async () => {
+      grantVesting = anchor.web3.Keypair.generate();
+      let [_grantVestingSigner, _] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [grantVesting.publicKey.toBuffer()],
+          vestingProgram.programId,
+        );
+      grantVestingSigner = _grantVestingSigner;
+      grantTokenVault = await tokenMint.createAccount(grantVestingSigner);
+
+      await vestingProgram.rpc.initializeGrantVesting(
+        tgePercentage,
+        new anchor.BN(Math.floor(Date.now() / 1000) - 10),
+        vestingPeriod,
+        vestingClaimPeriod,
+        new anchor.BN(0),
+        [],
+        true,
+        {
+          accounts: {
+            vesting: grantVesting.publicKey,
+            tokenMint: tokenMint.publicKey,
+            tokenVault: grantTokenVault,
+            vestingSigner: grantVestingSigner,
+            signer: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+          signers: [grantVesting],
+        },
+      );
+    });
+
+    it('grant and claim tgeAmount', async () => {
+      const userVesting = await findUserVesting(wallet.publicKey);
+
+      await vestingProgram.rpc.grant(wallet.publicKey, grantAmount, {
+        accounts: {
+          vesting: grantVesting.publicKey,
+          userVesting,
+          tokenVault: grantTokenVault,
+          fundVault,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      });
+
+      const claimRecipient = await tokenMint.createAccount(wallet.publicKey);
+      await vestingProgram.rpc.claimGrant({
+        accounts: {
+          vesting: grantVesting.publicKey,
+          userVesting,
+          tokenMint: tokenMint.publicKey,
+          tokenVault: grantTokenVault,
+          claimRecipient,
+          vestingSigner: grantVestingSigner,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      const tgeAmount = grantAmount
+        .mul(tgePercentage)
+        .div(new anchor.BN('100000'));
+      const recipientAccount = await tokenMint.getAccountInfo(claimRecipient);
+      assert.equal(recipientAccount.amount.toString(), tgeAmount.toString());
+
+      const userVestingAccount =
+        await vestingProgram.account.userVestingAccount.fetch(userVesting);
+      assert.equal(
+        userVestingAccount.beneficiary.toString(),
+        wallet.publicKey.toString(),
+      );
+      assert.equal(
+        userVestingAccount.amount.toString(),
+        grantAmount.toString(),
+      );
+      assert.equal(userVestingAccount.claimed.toString(), tgeAmount.toString());
+    });
+
+    it('revoke returns unvested tokens to treasury', async () => {
+      const userVesting = await findUserVesting(wallet.publicKey);
+
+      await vestingProgram.rpc.grant(wallet.publicKey, grantAmount, {
+        accounts: {
+          vesting: grantVesting.publicKey,
+          userVesting,
+          tokenVault: grantTokenVault,
+          fundVault,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      });
+
+      const treasury = await tokenMint.createAccount(wallet.publicKey);
+      const revoke = async () => {
+        await vestingProgram.rpc.revoke({
+          accounts: {
+            vesting: grantVesting.publicKey,
+            userVesting,
+            tokenMint: tokenMint.publicKey,
+            tokenVault: grantTokenVault,
+            treasury,
+            vestingSigner: grantVestingSigner,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+      };
+      await revoke();
+
+      // Only the TGE amount vested before the first claim period.
+      const tgeAmount = grantAmount
+        .mul(tgePercentage)
+        .div(new anchor.BN('100000'));
+      const treasuryAccount = await tokenMint.getAccountInfo(treasury);
+      assert.equal(
+        treasuryAccount.amount.toString(),
+        grantAmount.sub(tgeAmount).toString(),
+      );
+
+      try {
+        await revoke();
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Grant already revoked.');
+      }
+
+      const claimRecipient = await tokenMint.createAccount(wallet.publicKey);
+      await vestingProgram.rpc.claimGrant({
+        accounts: {
+          vesting: grantVesting.publicKey,
+          userVesting,
+          tokenMint: tokenMint.publicKey,
+          tokenVault: grantTokenVault,
+          claimRecipient,
+          vestingSigner: grantVestingSigner,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        },
+      });
+
+      const recipientAccount = await tokenMint.getAccountInfo(claimRecipient);
+      assert.equal(recipientAccount.amount.toString(), tgeAmount.toString());
+      const tokenVaultAccount = await tokenMint.getAccountInfo(grantTokenVault);
+      assert.equal(tokenVaultAccount.amount.toString(), '0');
+    });
+
+    it('sweep dust sends only excess tokens to authority', async () => {
+      const userVesting = await findUserVesting(wallet.publicKey);
+
+      await vestingProgram.rpc.grant(wallet.publicKey, grantAmount, {
+        accounts: {
+          vesting: grantVesting.publicKey,
+          userVesting,
+          tokenVault: grantTokenVault,
+          fundVault,
+          signer: wallet.publicKey,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      });
+
+      const dustRecipient = await tokenMint.createAccount(wallet.publicKey);
+      const sweepDust = async () => {
+        await vestingProgram.rpc.sweepDust({
+          accounts: {
+            vesting: grantVesting.publicKey,
+            tokenMint: tokenMint.publicKey,
+            tokenVault: grantTokenVault,
+            dustRecipient,
+            vestingSigner: grantVestingSigner,
+            signer: wallet.publicKey,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          },
+        });
+      };
+
+      try {
+        await sweepDust();
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'No dust to sweep.');
+      }
+
+      await tokenMint.transfer(
+        fundVault,
+        grantTokenVault,
+        wallet.payer,
+        [],
+        1000,
+      );
+      await sweepDust();
+
+      const recipientAccount = await tokenMint.getAccountInfo(dustRecipient);
+      assert.equal(recipientAccount.amount.toString(), '1000');
+      const tokenVaultAccount = await tokenMint.getAccountInfo(grantTokenVault);
+      assert.equal(
+        tokenVaultAccount.amount.toString(),
+        grantAmount.toString(),
+      );
+    });
+
+    it('batch grant creates and tops up grants', async () => {
+      const beneficiaries = [
+        anchor.web3.Keypair.generate().publicKey,
+        anchor.web3.Keypair.generate().publicKey,
+      ];
+      const userVestings = await Promise.all(
+        beneficiaries.map((x) => findUserVesting(x)),
+      );
+      const batchGrant = async () => {
+        await vestingProgram.rpc.batchGrant(
+          beneficiaries.map((beneficiary) => ({
+            beneficiary,
+            amount: grantAmount,
+          })),
+          {
+            accounts: {
+              vesting: grantVesting.publicKey,
+              tokenVault: grantTokenVault,
+              fundVault,
+              signer: wallet.publicKey,
+              tokenProgram: TOKEN_PROGRAM_ID,
+              systemProgram: anchor.web3.SystemProgram.programId,
+            },
+            remainingAccounts: userVestings.map((pubkey) => ({
+              pubkey,
+              isWritable: true,
+              isSigner: false,
+            })),
+          },
+        );
+      };
+
+      await batchGrant();
+      await batchGrant();
+
+      for (let i = 0; i < beneficiaries.length; i += 1) {
+        const userVestingAccount =
+          await vestingProgram.account.userVestingAccount.fetch(
+            userVestings[i],
+          );
+        assert.equal(
+          userVestingAccount.beneficiary.toString(),
+          beneficiaries[i].toString(),
+        );
+        assert.equal(
+          userVestingAccount.amount.toString(),
+          grantAmount.muln(2).toString(),
+        );
+      }
+
+      const vestingAccount = await vestingProgram.account.vesting.fetch(
+        grantVesting.publicKey,
+      );
+      assert.equal(vestingAccount.grant, true);
+      assert.equal(
+        vestingAccount.amount.toString(),
+        grantAmount.muln(4).toString(),
+      );
+      const tokenVaultAccount = await tokenMint.getAccountInfo(grantTokenVault);
+      assert.equal(
+        tokenVaultAccount.amount.toString(),
+        grantAmount.muln(4).toString(),
+      );
+    });
   });
 });
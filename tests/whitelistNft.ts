@@ -38,25 +38,91 @@ describe('whitelist-nft', () => {
     });
   });
 
-  describe('update_owner', () => {
-    it('update new owner', async () => {
-      let newOwner = anchor.web3.Keypair.generate();
+  describe('transfer ownership', () => {
+    let newOwner: anchor.web3.Keypair;
 
-      await whitelistProgram.rpc.updateOwner(newOwner.publicKey, {
+    beforeEach(async () => {
+      newOwner = anchor.web3.Keypair.generate();
+
+      await whitelistProgram.rpc.proposeOwner(newOwner.publicKey, {
         accounts: {
           config: whitelistConfig.publicKey,
           signer: wallet.publicKey,
         },
       });
+    });
 
-      const whitelistAccount =
+    it('proposed owner accepts', async () => {
+      let whitelistAccount =
         await whitelistProgram.account.whitelistConfig.fetch(
           whitelistConfig.publicKey,
         );
       assert.equal(
         whitelistAccount.owner.toString(),
+        wallet.publicKey.toString(),
+      );
+      assert.equal(
+        whitelistAccount.pendingOwner.toString(),
         newOwner.publicKey.toString(),
       );
+
+      await whitelistProgram.rpc.acceptOwner({
+        accounts: {
+          config: whitelistConfig.publicKey,
+          signer: newOwner.publicKey,
+        },
+        signers: [newOwner],
+      });
+
+      whitelistAccount = await whitelistProgram.account.whitelistConfig.fetch(
+        whitelistConfig.publicKey,
+      );
+      assert.equal(
+        whitelistAccount.owner.toString(),
+        newOwner.publicKey.toString(),
+      );
+      assert.equal(
+        whitelistAccount.pendingOwner.toString(),
+        anchor.web3.PublicKey.default.toString(),
+      );
+    });
+
+    it('only the proposed owner can accept', async () => {
+      try {
+        await whitelistProgram.rpc.acceptOwner({
+          accounts: {
+            config: whitelistConfig.publicKey,
+            signer: wallet.publicKey,
+          },
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Signer is not the pending owner.');
+      }
+    });
+
+    it('update_owner only proposes the owner', async () => {
+      let otherOwner = anchor.web3.Keypair.generate();
+
+      await whitelistProgram.rpc.updateOwner(otherOwner.publicKey, {
+        accounts: {
+          config: whitelistConfig.publicKey,
+          signer: wallet.publicKey,
+        },
+      });
+
+      const whitelistAccount =
+        await whitelistProgram.account.whitelistConfig.fetch(
+          whitelistConfig.publicKey,
+        );
+      assert.equal(
+        whitelistAccount.owner.toString(),
+        wallet.publicKey.toString(),
+      );
+      assert.equal(
+        whitelistAccount.pendingOwner.toString(),
+        otherOwner.publicKey.toString(),
+      );
     });
   });
 
@@ -93,6 +159,77 @@ describe('whitelist-nft', () => {
     });
   });
 
+  describe('batch_add_whitelist', () => {
+    it('whitelist multiple mints', async () => {
+      let mints = [
+        anchor.web3.Keypair.generate().publicKey,
+        anchor.web3.Keypair.generate().publicKey,
+      ];
+      let whitelistAccounts: anchor.web3.PublicKey[] = [];
+      for (const mint of mints) {
+        let [whitelistAccount, _] =
+          await anchor.web3.PublicKey.findProgramAddress(
+            [whitelistConfig.publicKey.toBuffer(), mint.toBuffer()],
+            whitelistProgram.programId,
+          );
+        whitelistAccounts.push(whitelistAccount);
+      }
+
+      await whitelistProgram.rpc.batchAddWhitelist(mints, {
+        accounts: {
+          config: whitelistConfig.publicKey,
+          signer: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+        remainingAccounts: whitelistAccounts.map((pubkey) => ({
+          pubkey,
+          isWritable: true,
+          isSigner: false,
+        })),
+      });
+
+      for (let i = 0; i < mints.length; i += 1) {
+        const whitelistAccountAccount =
+          await whitelistProgram.account.whitelistAccount.fetch(
+            whitelistAccounts[i],
+          );
+        assert.equal(
+          whitelistAccountAccount.config.toString(),
+          whitelistConfig.publicKey.toString(),
+        );
+        assert.equal(
+          whitelistAccountAccount.account.toString(),
+          mints[i].toString(),
+        );
+        assert.equal(whitelistAccountAccount.whitelisted, true);
+      }
+    });
+
+    it('fails with an unexpected account', async () => {
+      let mint = anchor.web3.Keypair.generate().publicKey;
+
+      try {
+        await whitelistProgram.rpc.batchAddWhitelist([mint], {
+          accounts: {
+            config: whitelistConfig.publicKey,
+            signer: wallet.publicKey,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          },
+          remainingAccounts: [
+            {
+              pubkey: anchor.web3.Keypair.generate().publicKey,
+              isWritable: true,
+              isSigner: false,
+            },
+          ],
+        });
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(err.msg, 'Invalid whitelist account.');
+      }
+    });
+  });
+
   describe('remove_whitelist', () => {
     let user = anchor.web3.Keypair.generate();
     let whitelistAccount: anchor.web3.PublicKey;
@@ -131,4 +268,93 @@ describe('whitelist-nft', () => {
       } catch {}
     });
   });
+
+  describe('add_collection', () => {
+    it('whitelist collection', async () => {
+      let collection = anchor.web3.Keypair.generate();
+
+      let [whitelistCollection, _] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [
+            Buffer.from('collection'),
+            whitelistConfig.publicKey.toBuffer(),
+            collection.publicKey.toBuffer(),
+          ],
+          whitelistProgram.programId,
+        );
+
+      await whitelistProgram.rpc.addCollection(collection.publicKey, {
+        accounts: {
+          config: whitelistConfig.publicKey,
+          whitelistCollection,
+          signer: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      });
+
+      const whitelistCollectionAccount =
+        await whitelistProgram.account.whitelistCollection.fetch(
+          whitelistCollection,
+        );
+      assert.equal(
+        whitelistCollectionAccount.config.toString(),
+        whitelistConfig.publicKey.toString(),
+      );
+      assert.equal(
+        whitelistCollectionAccount.collection.toString(),
+        collection.publicKey.toString(),
+      );
+      assert.equal(whitelistCollectionAccount.whitelisted, true);
+    });
+  });
+
+  describe('remove_collection', () => {
+    let collection = anchor.web3.Keypair.generate();
+    let whitelistCollection: anchor.web3.PublicKey;
+
+    beforeEach(async () => {
+      let [whitelistCollection_, _] =
+        await anchor.web3.PublicKey.findProgramAddress(
+          [
+            Buffer.from('collection'),
+            whitelistConfig.publicKey.toBuffer(),
+            collection.publicKey.toBuffer(),
+          ],
+          whitelistProgram.programId,
+        );
+
+      whitelistCollection = whitelistCollection_;
+
+      await whitelistProgram.rpc.addCollection(collection.publicKey, {
+        accounts: {
+          config: whitelistConfig.publicKey,
+          whitelistCollection,
+          signer: wallet.publicKey,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        },
+      });
+    });
+
+    it('remove collection', async () => {
+      await whitelistProgram.rpc.removeCollection({
+        accounts: {
+          config: whitelistConfig.publicKey,
+          whitelistCollection,
+          signer: wallet.publicKey,
+        },
+      });
+
+      try {
+        await whitelistProgram.account.whitelistCollection.fetch(
+          whitelistCollection,
+        );
+        assert.fail('DO NOT ENTER HERE');
+      } catch (err) {
+        assert.equal(
+          err.message,
+          `Account does not exist ${whitelistCollection.toString()}`,
+        );
+      }
+    });
+  });
 });
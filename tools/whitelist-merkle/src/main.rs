@@ -0,0 +1,217 @@
+//! Reads `address,allocation` rows from a CSV file and prints the Merkle root
+//! and per-address proofs for `token_sale::deposit_with_whitelist_proof` as JSON.
+//!
+//! Usage: whitelist-merkle <whitelist.csv>
+
+use anchor_lang::prelude::Pubkey;
+use std::collections::HashSet;
+use std::str::FromStr;
+use std::{env, fs, process};
+use token_sale::merkle::{hash_pair, leaf};
+
+struct Entry {
+    user: Pubkey,
+    allocation: u64,
+}
+
+fn parse_csv(content: &str) -> Result<Vec<Entry>, String> {
+    let mut entries = Vec::new();
+    let mut seen = HashSet::new();
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut fields = line.split(',').map(str::trim);
+        let (address, allocation) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(address), Some(allocation), None) => (address, allocation),
+            _ => return Err(format!("line {}: expected `address,allocation`", i + 1)),
+        };
+        let user = match Pubkey::from_str(address) {
+            Ok(user) => user,
+            // Header row.
+            Err(_) if i == 0 => continue,
+            Err(_) => return Err(format!("line {}: invalid address `{}`", i + 1, address)),
+        };
+        let allocation = allocation
+            .parse::<u64>()
+            .map_err(|_| format!("line {}: invalid allocation `{}`", i + 1, allocation))?;
+        if !seen.insert(user) {
+            return Err(format!("line {}: duplicate address {}", i + 1, user));
+        }
+        entries.push(Entry { user, allocation });
+    }
+
+    if entries.is_empty() {
+        return Err("no whitelist entries".to_string());
+    }
+
+    Ok(entries)
+}
+
+/// Builds every tree level from the leaves up. An odd node is carried to the
+/// next level unchanged.
+fn build_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [a, b] => hash_pair(a, b),
+                [a] => *a,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+
+    levels
+}
+
+fn proof(levels: &[Vec<[u8; 32]>], mut index: usize) -> Vec<[u8; 32]> {
+    let mut proof = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+
+    proof
+}
+
+fn to_hex(bytes: &[u8; 32]) -> String {
+    bytes.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+fn main() {
+    let path = match env::args().nth(1) {
+        Some(path) => path,
+        None => {
+            eprintln!("Usage: whitelist-merkle <whitelist.csv>");
+            process::exit(1);
+        }
+    };
+    let content = fs::read_to_string(&path).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    });
+    let entries = parse_csv(&content).unwrap_or_else(|err| {
+        eprintln!("{}: {}", path, err);
+        process::exit(1);
+    });
+
+    let levels = build_levels(
+        entries
+            .iter()
+            .map(|x| leaf(&x.user, x.allocation))
+            .collect(),
+    );
+    let root = levels.last().unwrap()[0];
+
+    println!("{{");
+    println!("  \"root\": \"{}\",", to_hex(&root));
+    println!("  \"proofs\": {{");
+    for (i, entry) in entries.iter().enumerate() {
+        let proof = proof(&levels, i)
+            .iter()
+            .map(|x| format!("\"{}\"", to_hex(x)))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let separator = if i + 1 < entries.len() { "," } else { "" };
+        println!(
+            "    \"{}\": {{ \"allocation\": \"{}\", \"proof\": [{}] }}{}",
+            entry.user, entry.allocation, proof, separator
+        );
+    }
+    println!("  }}");
+    println!("}}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use token_sale::merkle::verify;
+
+    fn whitelist(count: u64) -> (Vec<Entry>, Vec<Vec<[u8; 32]>>) {
+        let entries = (0..count)
+            .map(|i| Entry {
+                user: Pubkey::new_unique(),
+                allocation: 100 * (i + 1),
+            })
+            .collect::<Vec<_>>();
+        let levels = build_levels(
+            entries
+                .iter()
+                .map(|x| leaf(&x.user, x.allocation))
+                .collect(),
+        );
+
+        (entries, levels)
+    }
+
+    #[test]
+    fn proofs_verify_on_chain() {
+        for count in 1..=9 {
+            let (entries, levels) = whitelist(count);
+            let root = levels.last().unwrap()[0];
+            for (i, entry) in entries.iter().enumerate() {
+                assert!(verify(
+                    &proof(&levels, i),
+                    &root,
+                    leaf(&entry.user, entry.allocation)
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn odd_node_is_carried_up() {
+        let (entries, levels) = whitelist(3);
+        let last = leaf(&entries[2].user, entries[2].allocation);
+        assert_eq!(levels[1][1], last);
+        // The carried node has no sibling on the first level.
+        assert_eq!(proof(&levels, 2), vec![levels[1][0]]);
+    }
+
+    #[test]
+    fn single_leaf_is_root() {
+        let (entries, levels) = whitelist(1);
+        let root = levels.last().unwrap()[0];
+        assert_eq!(root, leaf(&entries[0].user, entries[0].allocation));
+        assert!(proof(&levels, 0).is_empty());
+        assert!(verify(&[], &root, root));
+    }
+
+    #[test]
+    fn wrong_amount_is_rejected() {
+        let (entries, levels) = whitelist(5);
+        let root = levels.last().unwrap()[0];
+        for (i, entry) in entries.iter().enumerate() {
+            assert!(!verify(
+                &proof(&levels, i),
+                &root,
+                leaf(&entry.user, entry.allocation + 1)
+            ));
+        }
+    }
+
+    #[test]
+    fn wrong_leaf_is_rejected() {
+        let (entries, levels) = whitelist(5);
+        let root = levels.last().unwrap()[0];
+        assert!(!verify(
+            &proof(&levels, 0),
+            &root,
+            leaf(&Pubkey::new_unique(), entries[0].allocation)
+        ));
+        // Another entry's proof does not verify this leaf.
+        assert!(!verify(
+            &proof(&levels, 1),
+            &root,
+            leaf(&entries[3].user, entries[3].allocation)
+        ));
+    }
+}